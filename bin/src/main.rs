@@ -1,6 +1,6 @@
 use std::{
   boxed::Box,
-  collections::{HashSet},
+  collections::{BTreeMap, BTreeSet, HashMap, HashSet},
   fs::{File},
   io::prelude::*,
   path::Path,
@@ -9,7 +9,7 @@ use clap::{Parser, crate_version, crate_authors};
 extern crate codegen2;
 use convert_case::{Case, Casing};
 use env_logger::Env;
-use log::{info, error};
+use log::{info, warn, error};
 use openapiv3::OpenAPI;
 extern crate serde_json;
 extern crate serde_yaml;
@@ -21,6 +21,56 @@ struct Flags {
   api_spec: String,
   #[clap()]
   out_file: String,
+  /// Use unsigned integer types for integers declaring `minimum: 0`
+  #[clap(long)]
+  unsigned: bool,
+  /// Traits to derive, in addition to Debug, Serialize and Deserialize, where legal for the type
+  #[clap(long, use_delimiter = true, require_delimiter = true, default_value = "Clone,PartialEq,Eq,Hash,Default")]
+  derive: Vec<String>,
+  /// Generate separate request (`<Name>Input`) types without readOnly properties, and leave writeOnly properties out of response types
+  #[clap(long)]
+  split_read_write: bool,
+}
+
+/// Derivable traits, in the order they are emitted.
+const DERIVES: [&str; 5] = ["Clone", "PartialEq", "Eq", "Hash", "Default"];
+
+/// How a `oneOf`/`anyOf` variant's type relates to the types of other variants.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum VariantKind {
+  /// A struct or enum generated for the variant's schema.
+  Nominal,
+  /// A primitive or container with a fixed type.
+  Concrete,
+  /// An alias that may be the same type as a concrete variant.
+  Alias,
+}
+
+/// Which of the optional derives a generated type can legally implement.
+/// `Clone` and `PartialEq` are implemented by every type the builder emits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TypeTraits {
+  pub eq: bool,
+  pub hash: bool,
+  pub default: bool,
+}
+
+impl TypeTraits {
+  fn all() -> Self {
+    TypeTraits {
+      eq: true,
+      hash: true,
+      default: true,
+    }
+  }
+
+  fn and(self, other: TypeTraits) -> Self {
+    TypeTraits {
+      eq: self.eq && other.eq,
+      hash: self.hash && other.hash,
+      default: self.default && other.default,
+    }
+  }
 }
 
 #[derive(Debug)]
@@ -30,7 +80,11 @@ struct PropType {
   pub nullable: bool,
   pub required: bool,
   pub serde_annotations: Vec<String>,
+  pub serde_with: Option<String>,
   pub doc: Option<Vec<String>>,
+  /// Expression for the schema's `default`, if it has one we can express.
+  pub default: Option<String>,
+  pub deprecated: bool,
 }
 
 impl PropType {
@@ -41,29 +95,65 @@ impl PropType {
       nullable: false,
       required: true,
       serde_annotations: vec![],
+      serde_with: None,
       doc: None,
+      default: None,
+      deprecated: false,
     }
   }
 
+  /// Whether a missing field is left as `None`, and `None` left out.
+  fn is_optional(&self) -> bool {
+    ! self.required
+  }
+
+  /// Optional and nullable, so `None` (absent) and `Some(None)` (null) differ
+  fn is_tristate(&self) -> bool {
+    self.nullable && self.is_optional()
+  }
+
   fn to_prop_type(&self) -> String {
     let mut type_ = self.type_.clone();
     if let Some(subtype) = &self.subtype {
       type_ = format!("{}<{}>", type_, subtype.to_prop_type());
     }
-    if self.nullable || ! self.required {
+    if self.nullable || self.is_optional() {
+      type_ = format!("Option<{}>", type_);
+    }
+    if self.is_tristate() {
       type_ = format!("Option<{}>", type_);
     }
     type_
   }
 
-  fn to_field(&self, name: &str) -> codegen2::Field {
+  fn to_field(&self, parent_name: &str, name: &str) -> codegen2::Field {
     let mut serde_annotations = self.serde_annotations.clone();
-    if ! self.required {
+    if self.is_optional() {
       serde_annotations.push("skip_serializing_if = \"Option::is_none\"".to_string());
     }
-    let mut field = codegen2::Field::new(name, self.to_prop_type());
+    if let Some(with) = &self.serde_with {
+      if self.is_tristate() {
+        serde_annotations.push(format!("with = \"{}::nullable\"", with));
+      } else if self.nullable || self.is_optional() {
+        serde_annotations.push(format!("with = \"{}::option\"", with));
+      } else {
+        serde_annotations.push(format!("with = \"{}\"", with));
+      }
+      if self.is_optional() {
+        serde_annotations.push("default".to_string());
+      }
+    } else if self.is_tristate() {
+      serde_annotations.push("default, deserialize_with = \"crate::nullable::deserialize\"".to_string());
+    }
+    if self.default.is_some() {
+      serde_annotations.push(format!("default = \"{}::default_{}\"", parent_name, unraw(name)));
+    }
+    let mut field = codegen2::Field::new(&format!("pub {}", name), self.to_prop_type());
     if ! serde_annotations.is_empty() {
-      field.annotation(vec![&format!("#[serde({})]", serde_annotations.join(", "))]);
+      field.annotation.push(format!("#[serde({})]", serde_annotations.join(", ")));
+    }
+    if self.deprecated {
+      field.annotation.push(deprecated_attr(None, None));
     }
     if let Some(doc) = &self.doc {
       field.doc(doc.iter().map(|v| v.as_str()).collect());
@@ -75,13 +165,125 @@ impl PropType {
 struct Builder {
   pub scope: Box<codegen2::Scope>,
   pub module_path: Vec<String>,
+  pub components: openapiv3::Components,
+  pub unsigned: bool,
+  pub untyped: Vec<String>,
+  pub derives: Vec<String>,
+  pub traits: HashMap<String, TypeTraits>,
+  /// References from one schema to another that are boxed to break cycles.
+  pub boxed: BTreeSet<(String, String)>,
+  /// The `components/schemas` entry being generated.
+  pub current: String,
+  /// Depth of heap-allocated containers (`Vec`, `HashMap`) being generated.
+  pub indirection: usize,
+  pub split_read_write: bool,
+  /// Schemas with separate request and response types.
+  pub split: BTreeSet<String>,
+  /// Whether the request (`Input`) variant of a split schema is being generated.
+  pub input: bool,
+  /// Inline types generated so far, by structure, with their module path.
+  pub inline_types: HashMap<String, (Vec<String>, String)>,
+  /// The types that aliases of reused inline types stand for, by path.
+  pub aliases: HashMap<String, String>,
+  /// Every type name in use, by path, with where it came from.
+  pub symbols: HashMap<String, String>,
+  /// Type names of `components/schemas` entries, and of `<name>-Input`
+  /// for split ones. Other components are keyed by their path, e.g.
+  /// `components/parameters/per-page`.
+  pub component_names: HashMap<String, String>,
+  /// `#[deprecated]` attributes of types deprecated by their operation, by path.
+  pub deprecations: HashMap<String, String>,
+  /// Entries of the `OPERATIONS` table, in generation order.
+  pub operations: Vec<String>,
 }
 
 impl Builder {
-  fn new() -> Self {
+  fn new(components: openapiv3::Components) -> Self {
     Builder {
       scope: Box::new(codegen2::Scope::new()),
       module_path: vec![],
+      components,
+      unsigned: false,
+      untyped: vec![],
+      derives: vec![],
+      traits: HashMap::new(),
+      boxed: BTreeSet::new(),
+      current: "".to_string(),
+      indirection: 0,
+      split_read_write: false,
+      split: BTreeSet::new(),
+      input: false,
+      inline_types: HashMap::new(),
+      aliases: HashMap::new(),
+      symbols: HashMap::new(),
+      component_names: HashMap::new(),
+      deprecations: HashMap::new(),
+      operations: vec![],
+    }
+  }
+
+  fn symbol_path(&self, name: &str) -> String {
+    self.module_path.iter().map(|v| v.as_str()).chain([name]).collect::<Vec<_>>().join("::")
+  }
+
+  fn is_taken(&self, name: &str) -> bool {
+    self.symbols.contains_key(&self.symbol_path(name))
+  }
+
+  /// Claim a type name in the current module for `origin`. Taken names get
+  /// the first free numeric suffix, in generation order.
+  fn claim(&mut self, name: &str, origin: &str) -> anyhow::Result<String> {
+    let name = type_ident(name);
+    if ! name.starts_with(|c: char| c.is_ascii_alphabetic()) || ! name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+      anyhow::bail!("{} generates the type name {:?}, which isn't a valid identifier", origin, name);
+    }
+    let mut res = name.clone();
+    let mut i = 2;
+    while self.is_taken(&res) {
+      res = format!("{}{}", name, i);
+      i += 1;
+    }
+    if res != name {
+      warn!("{} and {} both generate {}, naming the latter {}", self.symbols[&self.symbol_path(&name)], origin, name, res);
+    }
+    self.symbols.insert(self.symbol_path(&res), origin.to_string());
+    Ok(res)
+  }
+
+  /// Claim the names of all `components/schemas` entries up front, so that
+  /// references to them can be resolved before they are generated.
+  fn claim_component_names(&mut self) -> anyhow::Result<()> {
+    let names: Vec<String> = self.components.schemas.keys().cloned().collect();
+    for name in names {
+      let origin = format!("#/components/schemas/{}", name);
+      let type_name = self.claim(&name, &origin)?;
+      self.component_names.insert(name.clone(), type_name);
+      if self.split.contains(&name) {
+        let input = format!("{}-Input", name);
+        let type_name = self.claim(&input, &format!("{} (Input)", origin))?;
+        self.component_names.insert(input, type_name);
+      }
+    }
+    Ok(())
+  }
+
+  /// Make `path` the current module, public all the way down, importing
+  /// what generated types use.
+  fn new_type_module(&mut self, path: &[&str]) {
+    self.module_path = vec![];
+    for name in path {
+      self.module_path.push(name.to_string());
+      if let Some(module) = self.cur_module() {
+        module.vis("pub");
+      }
+    }
+    if let Some(module) = self.cur_module() {
+      module.import("serde", "{Serialize, Deserialize}");
+      module.import("std::collections", "HashMap");
+      module.import("crate::validate", "{Validate, Violation}");
+    }
+    for import in ["Serialize", "Deserialize", "HashMap", "Validate", "Violation"] {
+      self.symbols.insert(self.symbol_path(import), format!("the import of {}", import));
     }
   }
 
@@ -109,266 +311,2277 @@ impl Builder {
     Some(module)
   }
 
-  fn get_proptype_box(&mut self, parent_name: &str, prop_name: &str, val: &openapiv3::ReferenceOr<Box<openapiv3::Schema>>) -> PropType {
+  fn get_proptype_box(&mut self, parent_name: &str, prop_name: &str, val: &openapiv3::ReferenceOr<Box<openapiv3::Schema>>) -> anyhow::Result<PropType> {
     self.get_proptype(parent_name, prop_name, &val.clone().unbox())
   }
 
-  fn get_proptype(&mut self, parent_name: &str, prop_name: &str, val: &openapiv3::ReferenceOr<openapiv3::Schema>) -> PropType {
+  fn get_proptype(&mut self, parent_name: &str, prop_name: &str, val: &openapiv3::ReferenceOr<openapiv3::Schema>) -> anyhow::Result<PropType> {
     let mut res = PropType::new();
     match val {
       openapiv3::ReferenceOr::Item(item) => {
         res.nullable = item.schema_data.nullable;
+        res.deprecated = item.schema_data.deprecated;
+        let doc = schema_doc(&item.schema_data);
+        if ! doc.is_empty() {
+          res.doc = Some(doc);
+        }
+        let inline_name = match &item.schema_kind {
+          openapiv3::SchemaKind::Type(openapiv3::Type::String(string)) if ! string.enumeration.is_empty() => {
+            Some(format!("{}-{}", parent_name, prop_name))
+          },
+          openapiv3::SchemaKind::Type(openapiv3::Type::Object(obj)) if ! is_map(obj) => Some(format!("{}-{}", parent_name, prop_name)),
+          openapiv3::SchemaKind::AllOf {..} => Some(format!("{}-{}", parent_name, prop_name)),
+          openapiv3::SchemaKind::OneOf {..} | openapiv3::SchemaKind::AnyOf {..} => Some(format!("{}-{}-OneOf", parent_name, prop_name)),
+          _ => None,
+        };
+        let name = match inline_name {
+          Some(name) => match self.canonical_inline(&name, item)? {
+            (name, true) => name,
+            (type_, false) => {
+              res.default = default_expr(item, &type_);
+              res.type_ = type_;
+              return Ok(res);
+            },
+          },
+          None => "".to_string(),
+        };
+        let name = &name;
         match &item.schema_kind {
           openapiv3::SchemaKind::Type(type_) => match type_ {
-            openapiv3::Type::String(_) => res.type_ = "String".to_string(),
-            openapiv3::Type::Number(_) => res.type_ = "i64".to_string(),
-            openapiv3::Type::Integer(_) => res.type_ = "i64".to_string(),
+            openapiv3::Type::String(string) => {
+              if ! string.enumeration.is_empty() {
+                self.new_string_enum(name, &string.enumeration, &item.schema_data);
+                res.type_ = name.clone();
+              } else if let Some((type_, with)) = format_type(type_) {
+                res.type_ = type_.to_string();
+                res.serde_with = with.map(|with| with.to_string());
+              } else {
+                res.type_ = "String".to_string();
+              }
+            },
+            openapiv3::Type::Number(number) => res.type_ = number_type(number).to_string(),
+            openapiv3::Type::Integer(integer) => {
+              if let Some((type_, with)) = format_type(type_) {
+                res.type_ = type_.to_string();
+                res.serde_with = with.map(|with| with.to_string());
+              } else {
+                res.type_ = self.integer_type(integer).to_string();
+              }
+            },
             openapiv3::Type::Object(obj) => {
-              let name = &format!("{}-{}", parent_name, prop_name).to_case(Case::Pascal);
-              self.new_struct(name, obj);
-              res.type_ = name.clone();
+              if is_map(obj) {
+                let value_type = self.additional_properties_type(parent_name, prop_name, obj)?.unwrap();
+                res.type_ = format!("HashMap<String, {}>", value_type);
+              } else {
+                self.new_struct(name, obj, &item.schema_data, None)?;
+                res.type_ = name.clone();
+              }
             },
             openapiv3::Type::Array(arr) => {
               res.type_ = "Vec".to_string();
-              let mut subtype = Box::from(self.get_proptype_box(parent_name, prop_name, &arr.items));
+              self.indirection += 1;
+              let subtype = self.get_proptype_box(parent_name, prop_name, &arr.items);
+              self.indirection -= 1;
+              let mut subtype = Box::from(subtype?);
               if subtype.type_.is_empty() {
-                subtype.type_ = "HashMap<String, String>".to_string();
+                subtype.type_ = "serde_json::Value".to_string();
+                self.untyped.push(format!("{}/{}/items", parent_name, prop_name));
+              }
+              if subtype.serde_with.is_some() {
+                warn!("{}-{}: array items use the default serde format of {}", parent_name, prop_name, subtype.type_);
               }
               res.subtype = Some(subtype);
             },
             openapiv3::Type::Boolean {} => res.type_ = "bool".to_string(),
           },
           openapiv3::SchemaKind::OneOf {one_of} => {
-            let name = &format!("{}-{}-OneOf", parent_name, prop_name).to_case(Case::Pascal);
             let sub_name = &format!("{}-{}", parent_name, prop_name).to_case(Case::Pascal);
-            let enm = self.new_enum(name, sub_name, one_of);
-            enm.doc("OneOf");
-            enm.r#macro("#[serde(untagged)]");
+            self.new_enum(name, sub_name, one_of, &item.schema_data, Some("OneOf"))?;
+            res.type_ = name.clone();
+          },
+          openapiv3::SchemaKind::AllOf {all_of} => {
+            let obj = self.merge_all_of(name, all_of)?;
+            self.new_struct(name, &obj, &item.schema_data, Some("AllOf"))?;
             res.type_ = name.clone();
           },
-          openapiv3::SchemaKind::AllOf {all_of: _} => { error!("UNHANDLED: Prop type AllOf {}", parent_name) },
           openapiv3::SchemaKind::AnyOf {any_of} => {
-            let name = &format!("{}-{}-OneOf", parent_name, prop_name).to_case(Case::Pascal);
             let sub_name = &format!("{}-{}", parent_name, prop_name).to_case(Case::Pascal);
-            let enm = self.new_enum(name, sub_name, any_of);
-            enm.doc(&"AnyOf".to_string());
-            enm.r#macro("#[serde(untagged)]");
+            self.new_enum(name, sub_name, any_of, &item.schema_data, Some("AnyOf"))?;
             res.type_ = name.clone();
           },
           openapiv3::SchemaKind::Any(_) => {
-            res.type_ = "HashMap<String, String>".to_string();
-            res.serde_annotations.push("skip".to_string());
+            res.type_ = "serde_json::Value".to_string();
+            self.untyped.push(format!("{}/{}", parent_name, prop_name));
           },
         }
+        res.default = default_expr(item, &res.type_);
       }
       openapiv3::ReferenceOr::Reference {reference} => {
         if ! reference.starts_with("#/") {
           error!("Prop reference {} INVALID", reference);
         } else {
           let reference: String = reference.strip_prefix("#/").unwrap().to_string();
-          let mut reference_arr: Vec<String> = reference.split('/').map(|v| v.to_string()).collect();
-          let last = reference_arr.pop().unwrap();
+          let (modules, last) = reference.rsplit_once('/').unwrap_or(("", &reference));
+          let last = last.to_string();
+          let mut reference_arr: Vec<String> = modules.split('/').filter(|v| ! v.is_empty()).map(field_ident).collect();
           reference_arr.insert(0, "crate".to_string());
-          reference_arr.push(last.to_case(Case::Pascal));
+          if ! reference.starts_with("components/schemas/") {
+            reference_arr.push(self.component_names.get(&reference).cloned().unwrap_or_else(|| type_ident(&last)));
+          } else if self.input && self.split.contains(&last) {
+            reference_arr.push(self.component_names[&format!("{}-Input", last)].clone());
+          } else {
+            reference_arr.push(self.component_names.get(&last).cloned().unwrap_or_else(|| type_ident(&last)));
+          }
 
-          res.type_ = reference_arr.join("::");
+          let target = reference.strip_prefix("components/schemas/").map(|v| (self.current.clone(), v.to_string()));
+          if self.indirection == 0 && target.is_some_and(|edge| self.boxed.contains(&edge)) {
+            res.type_ = "Box".to_string();
+            res.subtype = Some(Box::new(PropType {type_: reference_arr.join("::"), ..PropType::new()}));
+          } else {
+            res.type_ = reference_arr.join("::");
+          }
           res.doc = Some(vec![format!("Ref {}", reference)]);
+          if let Some(schema) = self.field_schema(val) {
+            res.nullable = schema.schema_data.nullable;
+            res.deprecated = schema.schema_data.deprecated;
+            let doc = schema_doc(&schema.schema_data);
+            if ! doc.is_empty() {
+              res.doc = Some(doc);
+            }
+            res.default = default_expr(&schema, &res.type_);
+          }
+
+          if let Ok(schema) = self.resolve_schema(val) {
+            // A nullable target is null wherever it's used
+            res.nullable |= schema.schema_data.nullable;
+            if let openapiv3::SchemaKind::Type(type_) = &schema.schema_kind {
+              res.serde_with = format_type(type_).and_then(|(_, with)| with).map(|with| with.to_string());
+            }
+          }
         }
       },
     };
-    res
+    Ok(res)
   }
 
-  fn new_enum(&mut self, name: &str, sub_name: &str, val: &[openapiv3::ReferenceOr<openapiv3::Schema>]) -> &mut codegen2::Enum {
-    let mut variants: Vec<codegen2::Variant> = vec![];
+  /// Find the type for an inline schema that would be named `name`. Schemas
+  /// structurally identical to one seen before, ignoring docs, reuse its
+  /// type. Otherwise the type is named after the schema's `title` if it has
+  /// a free one. Either way `name` becomes an alias for the type, unless that
+  /// is taken. Returns the type, which is the alias for a reused type so its
+  /// name fits where it's used, and whether it still needs generating.
+  fn canonical_inline(&mut self, name: &str, item: &openapiv3::Schema) -> anyhow::Result<(String, bool)> {
+    let origin = name;
+    let name = type_ident(name);
+    let mut structure = serde_json::to_value(item).unwrap_or_default();
+    strip_docs(&mut structure);
+    // Boxing and readOnly/writeOnly splitting depend on where the type is
+    let boxed: Vec<&String> = self.boxed.iter()
+      .filter(|(from, _)| self.indirection == 0 && *from == self.current)
+      .map(|(_, to)| to)
+      .collect();
+    let key = format!("{}{:?}{}", self.input, boxed, structure);
 
-    for (i, variant) in val.iter().enumerate() {
-      let mut proptype = self.get_proptype(sub_name, &format!("{}", i), variant);
-      proptype.nullable = false;
-      variants.push(codegen2::Variant::new(&format!("{}({})", proptype.type_.rsplit("::").next().unwrap(), proptype.to_prop_type())));
+    if let Some((module_path, canonical)) = self.inline_types.get(&key) {
+      let type_ = if *module_path == self.module_path {
+        canonical.clone()
+      } else {
+        format!("crate::{}::{}", module_path.join("::"), canonical)
+      };
+      if self.is_taken(&name) {
+        return Ok((type_, false));
+      }
+      let name = self.claim(&name, origin)?;
+      self.cur_scope_or_module().raw(&format!("pub type {} = {};", name, type_));
+      self.aliases.insert(self.symbol_path(&name), type_);
+      return Ok((name, false));
     }
 
-    let enm = self.cur_scope_or_module().new_enum(&name.to_case(Case::Pascal))
-      .derive("Debug")
-      .derive("Serialize")
-      .derive("Deserialize");
+    let title = item.schema_data.title.as_deref()
+      .map(type_ident)
+      .filter(|title| ! self.is_taken(title));
+    let canonical = match title {
+      Some(title) => {
+        let canonical = self.claim(&title, origin)?;
+        if ! self.is_taken(&name) {
+          self.claim(&name, origin)?;
+          self.cur_scope_or_module().raw(&format!("pub type {} = {};", name, canonical));
+        }
+        canonical
+      },
+      None => self.claim(&name, origin)?,
+    };
+    self.inline_types.insert(key, (self.module_path.clone(), canonical.clone()));
+    Ok((canonical, true))
+  }
 
-    for variant in variants {
-      enm.push_variant(variant);
+  /// Generate the type for an entry of `components/schemas`.
+  fn new_schema(&mut self, name: &str, schema: &openapiv3::ReferenceOr<openapiv3::Schema>) -> anyhow::Result<()> {
+    match schema {
+      openapiv3::ReferenceOr::Item(schema) => {
+        match &schema.schema_kind {
+          openapiv3::SchemaKind::Type(type_) => {
+            match type_ {
+              openapiv3::Type::String(_) => { self.new_typedef(name, type_, &schema.schema_data)?; },
+              openapiv3::Type::Number(_) => { self.new_typedef(name, type_, &schema.schema_data)?; },
+              openapiv3::Type::Integer(_) => { self.new_typedef(name, type_, &schema.schema_data)?; },
+              openapiv3::Type::Object(val) if is_map(val) => { self.new_typedef(name, type_, &schema.schema_data)?; },
+              openapiv3::Type::Object(val) => { self.new_struct(name, val, &schema.schema_data, None)?; },
+              openapiv3::Type::Array(_) => { self.new_typedef(name, type_, &schema.schema_data)?; },
+              openapiv3::Type::Boolean {} => { self.new_typedef(name, type_, &schema.schema_data)?; },
+            }
+          },
+          openapiv3::SchemaKind::OneOf {one_of} => {
+            self.new_enum(name, name, one_of, &schema.schema_data, None)?;
+          },
+          openapiv3::SchemaKind::AllOf {all_of} => {
+            let obj = self.merge_all_of(name, all_of)?;
+            self.new_struct(name, &obj, &schema.schema_data, None)?;
+          },
+          openapiv3::SchemaKind::AnyOf {any_of} => {
+            self.new_enum(name, name, any_of, &schema.schema_data, None)?;
+          },
+          openapiv3::SchemaKind::Any(_) => {
+            self.new_anytypedef(name, &schema.schema_data);
+          },
+        }
+      },
+      openapiv3::ReferenceOr::Reference {reference} => {
+        info!("Found reference {} {}", name, reference);
+      }
     }
+    Ok(())
+  }
 
-    enm.vis("pub");
-
-    enm
+  /// Generate the types for an operation in `paths`. Everything in them is
+  /// sent to GitHub, so split schemas are used as `Input`.
+  fn new_operation(&mut self, method: &str, path: &str, path_item: &openapiv3::PathItem, operation: &openapiv3::Operation) -> anyhow::Result<()> {
+    let origin = format!("{} {}", method.to_uppercase(), path);
+    let operation_id = match &operation.operation_id {
+      Some(operation_id) => operation_id,
+      None => {
+        warn!("{} has no operationId, skipping it", origin);
+        return Ok(());
+      },
+    };
+    self.current = operation_id.clone();
+    self.operations.push(operation_meta(method, path, operation_id, operation));
+    self.input = true;
+    let deprecated = operation_deprecated_attr(operation);
+    self.new_params(operation_id, &origin, path_item, operation, deprecated.as_deref())?;
+    self.new_request(operation_id, &origin, operation, deprecated.as_deref())?;
+    self.input = false;
+    self.new_response(operation_id, &origin, operation, deprecated.as_deref())
   }
 
-  fn new_struct(&mut self, name: &str, val: &openapiv3::ObjectType) -> &mut codegen2::Struct {
-    let mut fields: Vec<codegen2::Field> = vec![];
-    let required: HashSet<&String> = val.required.iter().collect();
-    for (prop_name, prop) in &val.properties {
-      let mut proptype = self.get_proptype_box(name, prop_name, prop);
+  /// Generate `OPERATIONS`, the metadata of the operations generated so far.
+  fn new_operation_table(&mut self) {
+    let mut table = "/// Metadata of every operation, in the order of the spec's paths, and\n/// get, put, post, delete, options, head, patch, trace within a path.\npub const OPERATIONS: &[crate::metadata::OperationMeta] = &[\n".to_string();
+    for operation in &self.operations {
+      for line in operation.lines() {
+        table.push_str(&format!("    {}\n", line));
+      }
+    }
+    table.push_str("];");
+    self.cur_scope_or_module().raw(&table);
+  }
 
-      proptype.required = required.contains(prop_name);
+  /// Generate `<Op>Response`, with a variant for each documented status code
+  /// holding the decoded body, and `from_status_and_body` to decode one.
+  fn new_response(&mut self, operation_id: &str, origin: &str, operation: &openapiv3::Operation, deprecated: Option<&str>) -> anyhow::Result<()> {
+    let name = self.claim(&format!("{}-Response", operation_id), &format!("{} responses", origin))?;
+    if let Some(attr) = deprecated {
+      self.deprecations.insert(self.symbol_path(&name), attr.to_string());
+    }
+    if operation.responses.default.is_some() {
+      warn!("{}: default responses are not supported, skipping it", origin);
+    }
 
-      let new_prop_name = match prop_name.as_str() {
-        "+1" | "-1" => prop_name.to_string(),
-        _ => prop_name.to_case(Case::Snake),
+    let mut traits = TypeTraits {default: false, ..TypeTraits::all()};
+    let mut variants: Vec<codegen2::Variant> = vec![];
+    let mut decode = codegen2::Block::new("match status");
+    let mut uses_body = false;
+    for (status, response) in &operation.responses.responses {
+      let status = match status {
+        openapiv3::StatusCode::Code(status) => *status,
+        openapiv3::StatusCode::Range(_) => {
+          warn!("{}: {} responses are not supported, skipping them", origin, status);
+          continue;
+        },
       };
-
-      let field = match new_prop_name.as_str() {
-        "type" | "mod" | "ref" | "self" => {
-          proptype.serde_annotations.push(format!("rename=\"{}\"", prop_name));
-          proptype.to_field(&format!("{}_", new_prop_name))
+      let shared = match response {
+        openapiv3::ReferenceOr::Reference {reference} => reference.strip_prefix("#/")
+          .and_then(|path| self.component_names.get(path))
+          .map(|type_name| format!("crate::components::responses::{}", type_name)),
+        openapiv3::ReferenceOr::Item(_) => None,
+      };
+      let response = self.resolve_response(response)?;
+      let variant_name = status_variant_name(status);
+      let mut variant = codegen2::Variant::new(&variant_name);
+      for line in wrap_markdown(&format!("{}: {}", status, response.description.trim())) {
+        variant.annotation(format!("/// {}", line).trim_end());
+      }
+      let content = response.content.iter()
+        .find(|(content_type, _)| is_json(content_type))
+        .or_else(|| response.content.first());
+      match content {
+        Some((content_type, media_type)) if is_json(content_type) => {
+          let type_ = match &media_type.schema {
+            Some(schema) => {
+              traits = traits.and(self.schema_traits(schema));
+              match &shared {
+                Some(type_) => type_.clone(),
+                None => self.get_proptype(&name, &status.to_string(), schema)?.to_prop_type(),
+              }
+            },
+            None => "serde_json::Value".to_string(),
+          };
+          variant.tuple(&type_);
+          decode.line(format!("{} => serde_json::from_slice(body).map({}::{}).map_err(|error| crate::response::ResponseError::Body(status, error)),", status, name, variant_name));
         },
-        "+1" | "-1" => {
-          proptype.serde_annotations.push(format!("rename=\"{}\"", prop_name));
-          if new_prop_name == "+1" {
-            proptype.to_field(&"plus_one".to_string())
-          } else {
-            proptype.to_field(&"minus_one".to_string())
-          }
+        Some((content_type, _)) if content_type.starts_with("text/") => {
+          variant.tuple("String");
+          decode.line(format!("{} => Ok({}::{}(String::from_utf8_lossy(body).into_owned())),", status, name, variant_name));
         },
-        "$ref" => {
-          proptype.serde_annotations.push(format!("rename=\"{}\"", prop_name));
-          let mut chars = new_prop_name.chars();
-          chars.next();
-          proptype.to_field(&format!("{}_", chars.as_str()))
+        Some(_) => {
+          variant.tuple("Vec<u8>");
+          decode.line(format!("{} => Ok({}::{}(body.to_vec())),", status, name, variant_name));
         },
-        _ => match new_prop_name.chars().next() {
-          Some('@' | '$') => {
-            proptype.serde_annotations.push(format!("rename=\"{}\"", prop_name));
-            let mut chars = new_prop_name.chars();
-            chars.next();
-            proptype.to_field(&String::from(chars.as_str()))
-          },
-          _ => {
-            if &new_prop_name != prop_name {
-              proptype.serde_annotations.push(format!("rename=\"{}\"", prop_name))
-            }
-            proptype.to_field(&new_prop_name)
-          },
-        }
+        None => {
+          decode.line(format!("{} => Ok({}::{}),", status, name, variant_name));
+        },
+      }
+      uses_body |= content.is_some();
+      variants.push(variant);
+    }
+    decode.line("_ => Err(crate::response::ResponseError::UndocumentedStatus(status)),");
+
+    let derives = self.derives(traits);
+    let deprecated = self.deprecated_type_attr(&name, &Default::default());
+    let enm = self.cur_scope_or_module().new_enum(&name);
+    for derive in derives.into_iter().filter(|derive| ! matches!(*derive, "Serialize" | "Deserialize")) {
+      enm.derive(derive);
+    }
+    if let Some(attr) = deprecated {
+      enm.r#macro(&attr);
+    }
+    for variant in variants {
+      enm.push_variant(variant);
+    }
+    enm.vis("pub");
+    enm.doc(&format!("Responses documented for `{}`.", origin));
+
+    self.cur_scope_or_module().new_impl(&name)
+      .new_fn("from_status_and_body")
+      .doc("Decode the body of a response with the given status code.")
+      .vis("pub")
+      .arg("status", "u16")
+      .arg(if uses_body { "body" } else { "_body" }, "&[u8]")
+      .ret("Result<Self, crate::response::ResponseError>")
+      .push_block(decode);
+    Ok(())
+  }
+
+  /// Generate `<Op>Params`, with a struct each for the path, query and
+  /// header parameters of the operation. Parameters of the operation
+  /// override those of its path item with the same name and location.
+  fn new_params(&mut self, operation_id: &str, origin: &str, path_item: &openapiv3::PathItem, operation: &openapiv3::Operation, deprecated: Option<&str>) -> anyhow::Result<()> {
+    // Only the parameters listed, unlike a spec object without properties
+    let closed = || openapiv3::ObjectType {
+      additional_properties: Some(openapiv3::AdditionalProperties::Any(false)),
+      ..Default::default()
+    };
+    let mut groups = [
+      ("path", "Parameters in the path.", closed()),
+      ("query", "Parameters in the query string.", closed()),
+      ("header", "Parameters sent as headers.", closed()),
+    ];
+    for parameter in path_item.parameters.iter().chain(&operation.parameters) {
+      let (group, data) = match self.resolve_parameter(parameter)? {
+        openapiv3::Parameter::Path {parameter_data, ..} => (0, parameter_data),
+        openapiv3::Parameter::Query {parameter_data, ..} => (1, parameter_data),
+        openapiv3::Parameter::Header {parameter_data, ..} => (2, parameter_data),
+        openapiv3::Parameter::Cookie {parameter_data, ..} => {
+          warn!("{}: cookie parameter {} is not supported, skipping it", origin, parameter_data.name);
+          continue;
+        },
+      };
+      let obj = &mut groups[group].2;
+      obj.required.retain(|name| *name != data.name);
+      if data.required {
+        obj.required.push(data.name.clone());
+      }
+      let schema = match parameter {
+        openapiv3::ReferenceOr::Reference {reference} => openapiv3::ReferenceOr::Reference {reference: reference.clone()},
+        openapiv3::ReferenceOr::Item(_) => parameter_schema(&data),
       };
+      obj.properties.insert(data.name.clone(), schema);
+    }
 
-      fields.push(field);
+    let mut params = closed();
+    for (group, description, obj) in groups {
+      if obj.properties.is_empty() {
+        continue;
+      }
+      params.required.push(group.to_string());
+      params.properties.insert(group.to_string(), openapiv3::ReferenceOr::boxed_item(openapiv3::Schema {
+        schema_data: openapiv3::SchemaData {description: Some(description.to_string()), ..Default::default()},
+        schema_kind: openapiv3::SchemaKind::Type(openapiv3::Type::Object(obj)),
+      }));
     }
 
-    let str = self.cur_scope_or_module().new_struct(&name.to_case(Case::Pascal))
-      .derive("Debug")
-      .derive("Serialize")
-      .derive("Deserialize");
+    let name = self.claim(&format!("{}-Params", operation_id), &format!("{} parameters", origin))?;
+    if let Some(attr) = deprecated {
+      self.deprecations.insert(self.symbol_path(&name), attr.to_string());
+    }
+    self.new_struct(&name, &params, &operation_schema_data(operation), None)
+  }
 
-    for field in fields {
-      str.push_field(field);
+  /// Generate `<Op>Request` for the JSON request body of an operation, an
+  /// alias when the body refers to a component. Bare arrays and strings
+  /// accepted in place of an object are named after the operation's
+  /// `x-github.requestBodyParameterName`.
+  fn new_request(&mut self, operation_id: &str, origin: &str, operation: &openapiv3::Operation, deprecated: Option<&str>) -> anyhow::Result<()> {
+    let request_body = match &operation.request_body {
+      Some(request_body) => self.resolve_request_body(request_body)?,
+      None => return Ok(()),
+    };
+    let media_type = match request_body.content.iter().find(|(content_type, _)| is_json(content_type)) {
+      Some((_, media_type)) => media_type,
+      None => {
+        warn!("{}: request body has no JSON content, skipping it", origin);
+        return Ok(());
+      },
+    };
+    let mut schema = media_type.schema.clone().unwrap_or_else(|| openapiv3::ReferenceOr::Item(openapiv3::Schema {
+      schema_data: Default::default(),
+      schema_kind: openapiv3::SchemaKind::Any(Default::default()),
+    }));
+    if let openapiv3::ReferenceOr::Item(item) = &mut schema {
+      let parameter_name = operation.extensions.get("x-github")
+        .and_then(|x_github| x_github.get("requestBodyParameterName"))
+        .and_then(|name| name.as_str());
+      if let Some(parameter_name) = parameter_name {
+        name_bare_body(item, parameter_name);
+      }
     }
 
-    str.vis("pub");
+    let name = self.claim(&format!("{}-Request", operation_id), &format!("{} request body", origin))?;
+    if let Some(attr) = deprecated {
+      self.deprecations.insert(self.symbol_path(&name), attr.to_string());
+    }
+    self.new_named_schema(&name, &schema, request_body.description.as_deref())
+  }
 
-    str
+  /// Generate the type `name` for a schema, documented with `description`
+  /// unless it has its own, or an alias when the schema is a reference.
+  fn new_named_schema(&mut self, name: &str, schema: &openapiv3::ReferenceOr<openapiv3::Schema>, description: Option<&str>) -> anyhow::Result<()> {
+    match schema {
+      openapiv3::ReferenceOr::Item(item) => {
+        let mut item = item.clone();
+        if item.schema_data.description.is_none() {
+          item.schema_data.description = description.map(|v| v.to_string());
+        }
+        self.new_schema(name, &openapiv3::ReferenceOr::Item(item))
+      },
+      openapiv3::ReferenceOr::Reference {..} => {
+        let type_ = self.get_proptype(name, "", schema)?.to_prop_type();
+        let data = openapiv3::SchemaData {description: description.map(|v| v.to_string()), ..Default::default()};
+        let attr = self.raw_type_prefix(name, &data);
+        self.cur_scope_or_module().raw(&format!("{}pub type {} = {};", attr, name, type_));
+        Ok(())
+      },
+    }
   }
 
-  fn new_typedef(&mut self, name: &str, val: &openapiv3::Type) {
-    let name = name.to_case(Case::Pascal);
-    match val {
-      openapiv3::Type::String(_) => {
-        self.cur_scope_or_module().raw(&format!("pub type {} = String;", name));
+  /// Generate the type for an entry of `components/parameters`, which
+  /// operations referring to the parameter use for it.
+  fn new_parameter(&mut self, name: &str, parameter: &openapiv3::ReferenceOr<openapiv3::Parameter>) -> anyhow::Result<()> {
+    let path = format!("components/parameters/{}", name);
+    let type_name = self.claim(name, &format!("#/{}", path))?;
+    self.component_names.insert(path, type_name.clone());
+    match parameter {
+      openapiv3::ReferenceOr::Item(parameter) => {
+        let data = parameter_data(parameter);
+        self.new_named_schema(&type_name, &parameter_schema(data).unbox(), data.description.as_deref())
       },
-      openapiv3::Type::Number(_) => {
-        self.cur_scope_or_module().raw(&format!("pub type {} = i64;", name));
+      openapiv3::ReferenceOr::Reference {reference} => {
+        self.new_named_schema(&type_name, &openapiv3::ReferenceOr::Reference {reference: reference.clone()}, None)
       },
-      openapiv3::Type::Integer(_) => {
-        self.cur_scope_or_module().raw(&format!("pub type {} = i64;", name));
+    }
+  }
+
+  /// Generate the type for an entry of `components/headers`.
+  fn new_header(&mut self, name: &str, header: &openapiv3::ReferenceOr<openapiv3::Header>) -> anyhow::Result<()> {
+    let path = format!("components/headers/{}", name);
+    let type_name = self.claim(name, &format!("#/{}", path))?;
+    self.component_names.insert(path, type_name.clone());
+    match header {
+      openapiv3::ReferenceOr::Item(header) => {
+        self.new_named_schema(&type_name, &header_schema(header).unbox(), header.description.as_deref())
       },
-      openapiv3::Type::Object(object) => {
-        error!("UNHANDLED: new_typedef {} {:?}", name, object);
+      openapiv3::ReferenceOr::Reference {reference} => {
+        self.new_named_schema(&type_name, &openapiv3::ReferenceOr::Reference {reference: reference.clone()}, None)
       },
-      openapiv3::Type::Array(array) => {
-        let subtype = Box::from(self.get_proptype_box(&name, &"Arr".to_string(), &array.items));
-        self.cur_scope_or_module().raw(&format!("pub type {} = Vec<{}>;", name, subtype.to_prop_type()));
+    }
+  }
+
+  /// Generate the body type for an entry of `components/responses`, which
+  /// the `<Op>Response` variants of operations referring to it hold. A
+  /// response without a body is `()`.
+  fn new_shared_response(&mut self, name: &str, response: &openapiv3::ReferenceOr<openapiv3::Response>) -> anyhow::Result<()> {
+    let path = format!("components/responses/{}", name);
+    let type_name = self.claim(name, &format!("#/{}", path))?;
+    self.component_names.insert(path, type_name.clone());
+    let response = self.resolve_response(response)?;
+    let content = response.content.iter()
+      .find(|(content_type, _)| is_json(content_type))
+      .or_else(|| response.content.first());
+    let type_ = match content {
+      Some((content_type, media_type)) if is_json(content_type) => match &media_type.schema {
+        Some(schema) => return self.new_named_schema(&type_name, schema, Some(&response.description)),
+        None => "serde_json::Value",
       },
-      openapiv3::Type::Boolean {} => {
-        self.cur_scope_or_module().raw(&format!("pub type {} = bool;", name));
+      Some((content_type, _)) if content_type.starts_with("text/") => "String",
+      Some(_) => "Vec<u8>",
+      None => "()",
+    };
+    let data = openapiv3::SchemaData {description: Some(response.description.clone()), ..Default::default()};
+    let attr = self.raw_type_prefix(&type_name, &data);
+    self.cur_scope_or_module().raw(&format!("{}pub type {} = {};", attr, type_name, type_));
+    Ok(())
+  }
+
+  /// The `#[deprecated]` attribute for a type, from its operation or schema.
+  fn deprecated_type_attr(&self, name: &str, data: &openapiv3::SchemaData) -> Option<String> {
+    self.deprecations.get(&self.symbol_path(name)).cloned().or_else(|| deprecated_schema_attr(data))
+  }
+
+  /// Docs and attributes to put before a type written with `Scope::raw`.
+  fn raw_type_prefix(&self, name: &str, data: &openapiv3::SchemaData) -> String {
+    let mut res = String::new();
+    for line in schema_doc(data) {
+      if line.is_empty() {
+        res.push_str("///\n");
+      } else {
+        res.push_str(&format!("/// {}\n", line));
+      }
+    }
+    if let Some(attr) = self.deprecated_type_attr(name, data) {
+      res.push_str(&format!("{}\n", attr));
+    }
+    res
+  }
+
+  fn new_enum(&mut self, name: &str, sub_name: &str, val: &[openapiv3::ReferenceOr<openapiv3::Schema>], data: &openapiv3::SchemaData, doc: Option<&str>) -> anyhow::Result<()> {
+    let name = type_ident(name);
+    let mut variant_names: HashSet<String> = HashSet::new();
+    // (name, type, kind, whether the type is boxed to break a cycle)
+    let mut variants: Vec<(String, String, VariantKind, bool)> = vec![];
+    let mut serde_withs: Vec<Option<String>> = vec![];
+
+    for (i, variant) in val.iter().enumerate() {
+      let mut proptype = self.get_proptype(sub_name, &format!("{}", i), variant)?;
+      proptype.nullable = false;
+      let variant_name = unique_name(&mut variant_names, &variant_name(variant));
+      let kind = if self.is_nominal(variant) {
+        VariantKind::Nominal
+      } else if self.is_alias(variant) {
+        VariantKind::Alias
+      } else {
+        VariantKind::Concrete
+      };
+      let boxed = proptype.type_ == "Box";
+      variants.push((variant_name, proptype.to_prop_type(), kind, boxed));
+      serde_withs.push(proptype.serde_with);
+    }
+
+    let traits = val.iter()
+      .map(|variant| self.schema_traits(variant))
+      .fold(TypeTraits {default: false, ..TypeTraits::all()}, TypeTraits::and);
+    let derives = self.derives(traits);
+    let deprecated = self.deprecated_type_attr(&name, data);
+    let enm = self.cur_scope_or_module().new_enum(&name);
+    for derive in derives {
+      enm.derive(derive);
+    }
+    enm.r#macro("#[serde(untagged)]");
+    if let Some(attr) = deprecated {
+      enm.r#macro(&attr);
+    }
+
+    for ((variant_name, type_, _, _), with) in variants.iter().zip(&serde_withs) {
+      let field = match with {
+        Some(with) => format!("#[serde(with = {:?})] {}", with, type_),
+        None => type_.clone(),
+      };
+      enm.push_variant(codegen2::Variant::new(&format!("{}({})", variant_name, field)));
+    }
+
+    enm.vis("pub");
+
+    if let Some(doc) = type_doc(data, doc) {
+      enm.doc(&doc);
+    }
+
+    let imp = self.cur_scope_or_module().new_impl(&name);
+    for (variant_name, type_, _, boxed) in &variants {
+      let fn_name = variant_name.to_case(Case::Snake);
+      let (type_, value) = unbox(type_, *boxed, "value.as_ref()");
+      imp.new_fn(&format!("is_{}", fn_name))
+        .vis("pub")
+        .arg_ref_self()
+        .ret("bool")
+        .line(format!("matches!(self, {}::{}(_))", name, variant_name));
+      let mut block = codegen2::Block::new("match self");
+      block.line(format!("{}::{}(value) => Some({}),", name, variant_name, value));
+      if variants.len() > 1 {
+        block.line("_ => None,");
+      }
+      imp.new_fn(&format!("as_{}", fn_name))
+        .vis("pub")
+        .arg_ref_self()
+        .ret(format!("Option<&{}>", type_))
+        .push_block(block);
+    }
+
+    let mut validate_fn = codegen2::Function::new("validate_at");
+    let mut block = codegen2::Block::new("match self");
+    for ((variant_name, type_, _, _), variant) in variants.iter().zip(val) {
+      let (lines, items) = match self.resolve_schema(variant) {
+        Ok(schema) => self.constraint_checks(&format!("{}/{}", name, variant_name), &schema, "value", "*value", &["pointer".to_string()]),
+        Err(_) => (vec![], None),
+      };
+      if lines.is_empty() && items.is_none() {
+        block.line(format!("{}::{}(value) => value.validate_at(pointer, violations),", name, variant_name));
+        continue;
+      }
+      let mut arm = codegen2::Block::new(&format!("{}::{}(value) =>", name, variant_name));
+      for line in lines {
+        arm.line(line);
+      }
+      if let Some(items) = items {
+        arm.push_block(items);
+      }
+      if ! is_plain_type(type_) {
+        arm.line("value.validate_at(pointer, violations);");
+      }
+      block.push_block(arm);
+    }
+    validate_fn.push_block(block);
+    self.new_validate_impl(&name, validate_fn, true);
+
+    // Only one variant can be converted from a given type, and aliases may
+    // stand for the same type as any other variant that isn't nominal.
+    let non_nominal = variants.iter().filter(|(_, _, kind, _)| *kind != VariantKind::Nominal).count();
+    let has_alias = variants.iter().any(|(_, _, kind, _)| *kind == VariantKind::Alias);
+    let same_types: Vec<String> = variants.iter()
+      .map(|(_, type_, _, _)| self.aliases.get(&self.symbol_path(type_)).unwrap_or(type_).clone())
+      .collect();
+    for (i, (variant_name, type_, kind, boxed)) in variants.iter().enumerate() {
+      let unique = same_types.iter().filter(|other| **other == same_types[i]).count() == 1;
+      let (type_, value) = unbox(type_, *boxed, "Box::new(value)");
+      let from = match kind {
+        VariantKind::Nominal => true,
+        VariantKind::Concrete => ! has_alias,
+        VariantKind::Alias => non_nominal == 1,
+      };
+      if unique && from {
+        self.cur_scope_or_module().new_impl(&name)
+          .impl_trait(format!("From<{}>", type_))
+          .new_fn("from")
+          .arg("value", type_)
+          .ret("Self")
+          .line(format!("{}::{}({})", name, variant_name, value));
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Whether a schema's type is, or contains, an alias whose target depends on
+  /// another schema or on crate features.
+  fn is_alias(&self, val: &openapiv3::ReferenceOr<openapiv3::Schema>) -> bool {
+    let item = match val {
+      openapiv3::ReferenceOr::Reference {..} => return ! self.is_nominal(val),
+      openapiv3::ReferenceOr::Item(item) => item,
+    };
+    match &item.schema_kind {
+      openapiv3::SchemaKind::Type(openapiv3::Type::Array(arr)) => self.is_alias(&arr.items.clone().unbox()),
+      openapiv3::SchemaKind::Type(openapiv3::Type::Object(obj)) if is_map(obj) => match additional_properties(obj) {
+        Some(openapiv3::AdditionalProperties::Schema(schema)) => self.is_alias(schema),
+        _ => false,
       },
+      openapiv3::SchemaKind::Type(type_) => ! self.is_nominal(val) && format_type(type_).is_some(),
+      _ => false,
     }
   }
 
-  fn new_anytypedef(&mut self, name: &str) {
-    let name = name.to_case(Case::Pascal);
-    self.cur_scope_or_module().raw(&format!("pub type {} = HashMap<String, String>;", name));
+  /// Whether a schema generates a struct or enum of its own, rather than a
+  /// primitive or an alias that could stand for the same type as another.
+  fn is_nominal(&self, val: &openapiv3::ReferenceOr<openapiv3::Schema>) -> bool {
+    let schema = match self.resolve_schema(val) {
+      Ok(schema) => schema,
+      Err(_) => return false,
+    };
+    match &schema.schema_kind {
+      openapiv3::SchemaKind::Type(type_ @ openapiv3::Type::String(string)) => {
+        ! string.enumeration.is_empty()
+          || matches!(format_type(type_), Some(("crate::uri::UriTemplate" | "crate::email::Email", _)))
+      },
+      openapiv3::SchemaKind::Type(openapiv3::Type::Object(obj)) => ! is_map(obj),
+      openapiv3::SchemaKind::Type(_) | openapiv3::SchemaKind::Any(_) => false,
+      openapiv3::SchemaKind::OneOf {..} | openapiv3::SchemaKind::AllOf {..} | openapiv3::SchemaKind::AnyOf {..} => true,
+    }
   }
-}
 
-fn main() -> anyhow::Result<()> {
-  env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+  fn new_string_enum(&mut self, name: &str, val: &[String], data: &openapiv3::SchemaData) {
+    let name = type_ident(name);
+    let mut variants: Vec<codegen2::Variant> = vec![];
+    for (value, variant_name) in val.iter().zip(string_enum_variants(val)) {
+      let mut variant = codegen2::Variant::new(&variant_name);
+      variant.annotation(&format!("#[serde(rename={:?})]", value));
+      variants.push(variant);
+    }
 
-  let flags = Flags::parse();
-  let api_spec = Path::new(&flags.api_spec);
+    let mut unknown = codegen2::Variant::new("Unknown(String)");
+    unknown.annotation("#[serde(untagged)]");
+    variants.push(unknown);
 
-  let openapi: OpenAPI = match api_spec.extension().map(|v| v.to_str()).flatten() {
-    Some("json") => {
-      serde_json::from_reader(File::open(api_spec).unwrap()).map_err(anyhow::Error::from)
-    },
-    Some("yaml") => {
-      serde_yaml::from_reader(File::open(api_spec).unwrap()).map_err(anyhow::Error::from)
-    },
-    Some(ext) => Result::Err(anyhow::anyhow!("Invalid file extension {:?}", ext)),
-    None => Result::Err(anyhow::anyhow!("Invalid file type {:?}", api_spec))
-  }?;
+    let derives = self.derives(TypeTraits {default: false, ..TypeTraits::all()});
+    let deprecated = self.deprecated_type_attr(&name, data);
+    let enm = self.cur_scope_or_module().new_enum(&name);
+    for derive in derives {
+      enm.derive(derive);
+    }
 
-  if let Some(components) = openapi.components {
-    let mut builder = Builder::new();
-    builder.scope.raw("#![allow(non_camel_case_types, dead_code)]");
-    builder.module_path.push("components".to_string());
-    if let Some(module) = builder.cur_module() {
-      module.vis("pub");
-    }
-    builder.module_path.push("schemas".to_string());
-    if let Some(module) = builder.cur_module() {
-      module.vis("pub");
-      module.import("serde", "{Serialize, Deserialize}");
-      module.import("std::collections", "HashMap");
+    for variant in variants {
+      enm.push_variant(variant);
     }
-    for (name, schema) in components.schemas {
-      match schema {
-        openapiv3::ReferenceOr::Item(schema) => {
-          match schema.schema_kind {
-            openapiv3::SchemaKind::Type(type_) => {
-              match &type_ {
-                openapiv3::Type::String(_) => { builder.new_typedef(&name, &type_); },
-                openapiv3::Type::Number(_) => { builder.new_typedef(&name, &type_); },
-                openapiv3::Type::Integer(_) => { builder.new_typedef(&name, &type_); },
-                openapiv3::Type::Object(val) => { builder.new_struct(&name, val); },
-                openapiv3::Type::Array(_) => { builder.new_typedef(&name, &type_); },
-                openapiv3::Type::Boolean {} => { builder.new_typedef(&name, &type_); },
-              }
-            },
-            openapiv3::SchemaKind::OneOf {one_of} => {
-              let enm = builder.new_enum(&name, &"".to_string(), &one_of);
-              enm.r#macro("#[serde(untagged)]");
-            },
-            openapiv3::SchemaKind::AllOf {all_of: _} => {
-              error!("UNHANDLED: Component type AllOf");
-            },
-            openapiv3::SchemaKind::AnyOf {any_of} => {
-              let enm = builder.new_enum(&name, &"".to_string(), &any_of);
-              enm.r#macro("#[serde(untagged)]");
-            },
-            openapiv3::SchemaKind::Any(_) => {
-              builder.new_anytypedef(&name);
-            },
-          }
-        },
-        openapiv3::ReferenceOr::Reference {reference} => {
-          info!("Found reference {} {}", name, reference);
-        }
+
+    enm.vis("pub");
+
+    if let Some(doc) = type_doc(data, None) {
+      enm.doc(&doc);
+    }
+
+    if let Some(attr) = deprecated {
+      enm.r#macro(&attr);
+    }
+
+    self.new_validate_impl(&name, codegen2::Function::new("validate_at"), false);
+  }
+
+  fn new_struct(&mut self, name: &str, val: &openapiv3::ObjectType, data: &openapiv3::SchemaData, doc: Option<&str>) -> anyhow::Result<()> {
+    let mut fields: Vec<codegen2::Field> = vec![];
+    let mut new_fn = codegen2::Function::new("new");
+    let mut new_fn_fields: Vec<String> = vec![];
+    let mut default_fields: Vec<String> = vec![];
+    let mut default_fns: Vec<codegen2::Function> = vec![];
+    let mut validate_fn = codegen2::Function::new("validate_at");
+    let mut checks = false;
+    let struct_name = type_ident(name);
+    let mut field_names: HashSet<String> = HashSet::new();
+    if additional_properties(val).is_some() {
+      field_names.insert("additional_properties".to_string());
+    }
+    let required: HashSet<&String> = val.required.iter().collect();
+    for (prop_name, prop) in &val.properties {
+      if self.skip_property(prop) {
+        continue;
       }
+      let mut proptype = self.get_proptype_box(name, prop_name, prop)?;
+
+      proptype.required = required.contains(prop_name);
+      // Filling in the default of an optional field would send it in bodies
+      // built from `new`, e.g. resetting settings on a PATCH
+      if ! proptype.required {
+        proptype.default = None;
+      }
+
+      let field_name = unique_name(&mut field_names, &field_ident(prop_name));
+      if unraw(&field_name) != prop_name {
+        proptype.serde_annotations.push(format!("rename={:?}", prop_name));
+      }
+      let field = proptype.to_field(&struct_name, &field_name);
+
+      let field_name = field.name.strip_prefix("pub ").unwrap();
+      if let Some(default) = &proptype.default {
+        let mut default_fn = codegen2::Function::new(&format!("default_{}", unraw(field_name)));
+        default_fn.vis("pub").ret(proptype.to_prop_type()).line(default);
+        default_fns.push(default_fn);
+        new_fn_fields.push(format!("{}: {}::default_{}(),", field_name, struct_name, unraw(field_name)));
+        default_fields.push(format!("{}: {}::default_{}(),", field_name, struct_name, unraw(field_name)));
+      } else if proptype.required {
+        new_fn.arg(field_name, proptype.to_prop_type());
+        new_fn_fields.push(format!("{},", field_name));
+        if proptype.to_prop_type().starts_with("Option<") {
+          default_fields.push(format!("{}: None,", field_name));
+        } else {
+          default_fields.push(format!("{}: Default::default(),", field_name));
+        }
+      } else {
+        new_fn_fields.push(format!("{}: None,", field_name));
+        default_fields.push(format!("{}: None,", field_name));
+      }
+
+      checks |= self.validate_field(&mut validate_fn, prop, &proptype, field_name, prop_name);
+
+      fields.push(field);
+    }
+
+    if let Some(value_type) = self.additional_properties_type(name, "additional_properties", val)? {
+      let mut field = codegen2::Field::new("pub additional_properties", format!("HashMap<String, {}>", value_type));
+      field.annotation(vec!["#[serde(flatten)]"]);
+      fields.push(field);
+      new_fn_fields.push("additional_properties: HashMap::new(),".to_string());
+      default_fields.push("additional_properties: HashMap::new(),".to_string());
+      if ! is_plain_type(&value_type) {
+        validate_fn.line("self.additional_properties.validate_at(pointer, violations);");
+        checks = true;
+      }
+    }
+
+    // Defaults from the spec need a hand-written `Default`. `new` can still
+    // take required nullable fields, which default to `None`.
+    let mut traits = self.object_traits(val);
+    let default_impl = traits.default && ! default_fns.is_empty();
+    if default_impl {
+      traits.default = false;
+    }
+    let derives = self.derives(traits);
+    let name = struct_name;
+    let deprecated = self.deprecated_type_attr(&name, data);
+    if fields.is_empty() {
+      // codegen2 writes a struct without fields as a unit struct, which serde
+      // (de)serializes as `null` rather than `{}`
+      let mut raw = String::new();
+      for line in type_doc(data, doc).iter().flat_map(|doc| doc.lines()) {
+        raw.push_str(format!("/// {}", line).trim_end());
+        raw.push('\n');
+      }
+      raw.push_str(&format!("#[derive({})]\n", derives.join(", ")));
+      if let Some(attr) = deprecated {
+        raw.push_str(&format!("{}\n", attr));
+      }
+      raw.push_str(&format!("pub struct {} {{}}", name));
+      self.cur_scope_or_module().raw(&raw);
+    } else {
+      let str = self.cur_scope_or_module().new_struct(&name);
+      for derive in derives {
+        str.derive(derive);
+      }
+
+      for field in fields {
+        str.push_field(field);
+      }
+
+      if let Some(attr) = deprecated {
+        str.r#macro(&attr);
+      }
+
+      str.vis("pub");
+
+      if let Some(doc) = type_doc(data, doc) {
+        str.doc(&doc);
+      }
+    }
+
+    let mut block = codegen2::Block::new(&name);
+    for line in new_fn_fields {
+      block.line(line);
+    }
+    new_fn.vis("pub").ret("Self").push_block(block);
+    let imp = self.cur_scope_or_module().new_impl(&name);
+    imp.push_fn(new_fn);
+    for default_fn in default_fns {
+      imp.push_fn(default_fn);
+    }
+
+    self.new_validate_impl(&name, validate_fn, checks);
+
+    if default_impl && self.derives.iter().any(|derive| derive == "Default") {
+      let mut block = codegen2::Block::new(&name);
+      for line in default_fields {
+        block.line(line);
+      }
+      self.cur_scope_or_module().new_impl(&name)
+        .impl_trait("Default")
+        .new_fn("default")
+        .ret("Self")
+        .push_block(block);
+    }
+
+    Ok(())
+  }
+
+  /// Generate `validate_<name>` for an alias of a type with constraints,
+  /// which can't implement `Validate` for it, e.g. for a request body.
+  fn new_alias_validate_fn(&mut self, name: &str, type_: &str, val: &openapiv3::Type, data: &openapiv3::SchemaData) {
+    let schema = openapiv3::Schema {schema_data: data.clone(), schema_kind: openapiv3::SchemaKind::Type(val.clone())};
+    let (lines, items) = self.constraint_checks(name, &schema, "value", "*value", &["pointer".to_string()]);
+    if lines.is_empty() && items.is_none() {
+      return;
+    }
+    let mut validate_fn = codegen2::Function::new(&format!("validate_{}", name.to_case(Case::Snake)));
+    validate_fn
+      .doc(&format!("Check a [`{}`] found at `pointer`, adding what's wrong to `violations`.", name))
+      .vis("pub")
+      .arg("value", format!("&{}", name))
+      .arg("pointer", "&str")
+      .arg("violations", "&mut Vec<Violation>");
+    for line in lines {
+      validate_fn.line(line);
+    }
+    if let Some(items) = items {
+      validate_fn.push_block(items);
+    }
+    if ! is_plain_type(type_) {
+      validate_fn.line("value.validate_at(pointer, violations);");
+    }
+    self.cur_scope_or_module().push_fn(validate_fn);
+  }
+
+  fn new_typedef(&mut self, name: &str, val: &openapiv3::Type, data: &openapiv3::SchemaData) -> anyhow::Result<()> {
+    let name = type_ident(name);
+    let attr = self.raw_type_prefix(&name, data);
+    match val {
+      openapiv3::Type::String(string) => {
+        if ! string.enumeration.is_empty() {
+          self.new_string_enum(&name, &string.enumeration, data);
+        } else if let Some((type_, _)) = format_type(val) {
+          self.cur_scope_or_module().raw(&format!("{}pub type {} = {};", attr, name, type_));
+        } else {
+          self.cur_scope_or_module().raw(&format!("{}pub type {} = String;", attr, name));
+          self.new_alias_validate_fn(&name, "String", val, data);
+        }
+      },
+      openapiv3::Type::Number(number) => {
+        self.cur_scope_or_module().raw(&format!("{}pub type {} = {};", attr, name, number_type(number)));
+        self.new_alias_validate_fn(&name, number_type(number), val, data);
+      },
+      openapiv3::Type::Integer(integer) => {
+        let type_ = format_type(val).map_or_else(|| self.integer_type(integer), |(type_, _)| type_);
+        self.cur_scope_or_module().raw(&format!("{}pub type {} = {};", attr, name, type_));
+        self.new_alias_validate_fn(&name, type_, val, data);
+      },
+      openapiv3::Type::Object(object) => {
+        match self.additional_properties_type(&name, "Value", object)? {
+          Some(value_type) if object.properties.is_empty() => {
+            self.cur_scope_or_module().raw(&format!("{}pub type {} = HashMap<String, {}>;", attr, name, value_type));
+          },
+          _ => error!("UNHANDLED: new_typedef {} {:?}", name, object),
+        }
+      },
+      openapiv3::Type::Array(array) => {
+        self.indirection += 1;
+        let subtype = self.get_proptype_box(&name, "Arr", &array.items);
+        self.indirection -= 1;
+        let subtype = Box::from(subtype?);
+        let type_ = format!("Vec<{}>", subtype.to_prop_type());
+        self.cur_scope_or_module().raw(&format!("{}pub type {} = {};", attr, name, type_));
+        self.new_alias_validate_fn(&name, &type_, val, data);
+      },
+      openapiv3::Type::Boolean {} => {
+        self.cur_scope_or_module().raw(&format!("{}pub type {} = bool;", attr, name));
+      },
+    }
+    Ok(())
+  }
+
+  /// The derives to emit for a type with the given traits.
+  fn derives(&self, traits: TypeTraits) -> Vec<&'static str> {
+    let mut derives = vec!["Debug"];
+    for derive in DERIVES {
+      let legal = match derive {
+        "Eq" => traits.eq,
+        "Hash" => traits.hash,
+        "Default" => traits.default,
+        _ => true,
+      };
+      if legal && self.derives.iter().any(|v| v == derive) {
+        derives.push(derive);
+      }
+    }
+    derives.push("Serialize");
+    derives.push("Deserialize");
+    derives
+  }
+
+  /// Find the references to box so that no schema contains itself by value.
+  /// Every such cycle lies within a strongly connected component of the
+  /// reference graph, and the back edges of a depth-first search through
+  /// the component break all of its cycles.
+  fn compute_boxes(&mut self) {
+    let graph: BTreeMap<String, BTreeSet<String>> = self.components.schemas.iter()
+      .map(|(name, schema)| {
+        let mut refs = BTreeSet::new();
+        self.schema_refs(schema, &mut refs);
+        (name.clone(), refs)
+      })
+      .collect();
+    for component in strongly_connected(&graph) {
+      let first = component.iter().next().unwrap();
+      if component.len() == 1 && ! graph[first].contains(first) {
+        continue;
+      }
+      let mut visited = BTreeSet::new();
+      let mut stack = vec![];
+      back_edges(&graph, &component, first, &mut visited, &mut stack, &mut self.boxed);
+      for (from, to) in &self.boxed {
+        if component.contains(from) {
+          info!("Boxed reference from {} to {} to break the cycle through {}", from, to, component.iter().cloned().collect::<Vec<_>>().join(", "));
+        }
+      }
+    }
+  }
+
+  /// The `components/schemas` entries a schema contains by value, i.e. not
+  /// behind a `Vec` or `HashMap`. `allOf` members aren't contained, but
+  /// their properties are, as they are merged into the schema's struct.
+  fn schema_refs(&self, val: &openapiv3::ReferenceOr<openapiv3::Schema>, refs: &mut BTreeSet<String>) {
+    let item = match val {
+      openapiv3::ReferenceOr::Reference {reference} => {
+        if let Some(name) = reference.strip_prefix("#/components/schemas/") {
+          refs.insert(name.to_string());
+        }
+        return;
+      },
+      openapiv3::ReferenceOr::Item(item) => item,
+    };
+    match &item.schema_kind {
+      openapiv3::SchemaKind::Type(openapiv3::Type::Object(obj)) => {
+        for prop in obj.properties.values() {
+          self.schema_refs(&prop.clone().unbox(), refs);
+        }
+      },
+      openapiv3::SchemaKind::AllOf {all_of} => match self.merge_all_of("", all_of) {
+        Ok(obj) => {
+          for prop in obj.properties.values() {
+            self.schema_refs(&prop.clone().unbox(), refs);
+          }
+        },
+        Err(_) => {
+          for member in all_of {
+            self.schema_refs(member, refs);
+          }
+        },
+      },
+      openapiv3::SchemaKind::OneOf {one_of: members}
+        | openapiv3::SchemaKind::AnyOf {any_of: members} => {
+        for member in members {
+          self.schema_refs(member, refs);
+        }
+      },
+      _ => {},
+    }
+  }
+
+  /// `impl Validate` with the given `validate_at`, which can be left empty.
+  fn new_validate_impl(&mut self, name: &str, mut validate_fn: codegen2::Function, checks: bool) {
+    let (pointer, violations) = if checks { ("pointer", "violations") } else { ("_pointer", "_violations") };
+    validate_fn
+      .arg_ref_self()
+      .arg(pointer, "&str")
+      .arg(violations, "&mut Vec<Violation>");
+    self.cur_scope_or_module().new_impl(name)
+      .impl_trait("Validate")
+      .push_fn(validate_fn);
+  }
+
+  /// Add the checks for a struct field to `validate_at`: the constraints of
+  /// its schema, then the checks of the values it contains. Returns whether
+  /// there were any.
+  fn validate_field(&self, validate_fn: &mut codegen2::Function, prop: &openapiv3::ReferenceOr<Box<openapiv3::Schema>>, proptype: &PropType, field_name: &str, prop_name: &str) -> bool {
+    let optional = proptype.nullable || proptype.is_optional();
+    let (value_ref, value) = if optional {
+      ("value".to_string(), "*value".to_string())
+    } else {
+      (format!("&self.{}", field_name), format!("self.{}", field_name))
+    };
+    let pointer = ["pointer".to_string(), format!("{:?}", prop_name.replace('~', "~0").replace('/', "~1"))];
+
+    let (mut lines, items) = match self.resolve_schema(&prop.clone().unbox()) {
+      Ok(schema) => self.constraint_checks(&format!("{}/{}", self.current, prop_name), &schema, &value_ref, &value, &pointer),
+      Err(_) => (vec![], None),
+    };
+    if ! is_plain_type(&proptype.to_prop_type()) {
+      let value_ref = value_ref.strip_prefix('&').unwrap_or(&value_ref);
+      lines.push(format!("{}.validate_at({}, violations);", value_ref, pointer_expr(&pointer)));
+    }
+
+    if lines.is_empty() && items.is_none() {
+      return false;
+    }
+    if optional {
+      let pattern = if proptype.is_tristate() { "Some(Some(value))" } else { "Some(value)" };
+      let mut block = codegen2::Block::new(&format!("if let {} = &self.{}", pattern, field_name));
+      for line in lines {
+        block.line(line);
+      }
+      if let Some(items) = items {
+        block.push_block(items);
+      }
+      validate_fn.push_block(block);
+    } else {
+      for line in lines {
+        validate_fn.line(line);
+      }
+      if let Some(items) = items {
+        validate_fn.push_block(items);
+      }
+    }
+    true
+  }
+
+  /// The checks of a value against the constraints of its schema, and a loop
+  /// checking the items of an array against theirs. `value_ref` is a
+  /// reference to the value and `pointer` the segments of its JSON pointer.
+  /// `origin` names the schema in warnings.
+  fn constraint_checks(&self, origin: &str, schema: &openapiv3::Schema, value_ref: &str, value: &str, pointer: &[String]) -> (Vec<String>, Option<codegen2::Block>) {
+    let mut lines = vec![];
+    let mut items = None;
+    let pointer_ref = pointer_expr(pointer);
+    match &schema.schema_kind {
+      openapiv3::SchemaKind::Type(type_ @ openapiv3::Type::String(string)) if string.enumeration.is_empty() && format_type(type_).is_none() => {
+        if let Some(min) = string.min_length {
+          lines.push(format!("crate::validate::min_length({}, {}, {}, violations);", value_ref, min, pointer_ref));
+        }
+        if let Some(max) = string.max_length {
+          lines.push(format!("crate::validate::max_length({}, {}, {}, violations);", value_ref, max, pointer_ref));
+        }
+        if let Some(pattern) = &string.pattern {
+          if regex::Regex::new(pattern).is_ok() {
+            lines.push(format!("crate::validate::pattern({}, {:?}, {}, violations);", value_ref, pattern, pointer_ref));
+          } else {
+            warn!("{}: the regex crate can't compile pattern {:?}, so it isn't checked", origin, pattern);
+          }
+        }
+      },
+      openapiv3::SchemaKind::Type(type_ @ openapiv3::Type::Integer(integer)) if format_type(type_).is_none() => {
+        let value = format!("{} as f64", value);
+        if let Some(min) = integer.minimum {
+          lines.push(format!("crate::validate::minimum({}, {:?}, {}, {}, violations);", value, min as f64, integer.exclusive_minimum, pointer_ref));
+        }
+        if let Some(max) = integer.maximum {
+          lines.push(format!("crate::validate::maximum({}, {:?}, {}, {}, violations);", value, max as f64, integer.exclusive_maximum, pointer_ref));
+        }
+      },
+      openapiv3::SchemaKind::Type(openapiv3::Type::Number(number)) => {
+        let value = if number_type(number) == "f64" { value.to_string() } else { format!("{} as f64", value) };
+        if let Some(min) = number.minimum {
+          lines.push(format!("crate::validate::minimum({}, {:?}, {}, {}, violations);", value, min, number.exclusive_minimum, pointer_ref));
+        }
+        if let Some(max) = number.maximum {
+          lines.push(format!("crate::validate::maximum({}, {:?}, {}, {}, violations);", value, max, number.exclusive_maximum, pointer_ref));
+        }
+      },
+      openapiv3::SchemaKind::Type(openapiv3::Type::Array(array)) => {
+        if let Some(min) = array.min_items {
+          lines.push(format!("crate::validate::min_items({}, {}, {}, violations);", value_ref, min, pointer_ref));
+        }
+        if let Some(max) = array.max_items {
+          lines.push(format!("crate::validate::max_items({}, {}, {}, violations);", value_ref, max, pointer_ref));
+        }
+        // Items of nested arrays get their own names, numbered by how many
+        // indexes are already in the pointer
+        let (index, item) = match pointer.iter().filter(|segment| segment.starts_with('i')).count() {
+          0 => ("i".to_string(), "item".to_string()),
+          depth => (format!("i{}", depth), format!("item{}", depth)),
+        };
+        let mut item_pointer = pointer.to_vec();
+        item_pointer.push(index.clone());
+        let (item_lines, item_items) = match self.resolve_schema(&array.items.clone().unbox()) {
+          Ok(schema) if ! schema.schema_data.nullable => {
+            self.constraint_checks(&format!("{}/items", origin), &schema, &item, &format!("*{}", item), &item_pointer)
+          },
+          _ => (vec![], None),
+        };
+        if ! item_lines.is_empty() || item_items.is_some() {
+          let value_ref = value_ref.strip_prefix('&').unwrap_or(value_ref);
+          let mut block = codegen2::Block::new(&format!("for ({}, {}) in {}.iter().enumerate()", index, item, value_ref));
+          for line in item_lines {
+            block.line(line);
+          }
+          if let Some(item_items) = item_items {
+            block.push_block(item_items);
+          }
+          items = Some(block);
+        }
+      },
+      _ => {},
+    }
+    (lines, items)
+  }
+
+  /// Find the schemas that need separate request and response types, because
+  /// they or the schemas they contain have readOnly or writeOnly properties.
+  fn compute_split(&mut self) {
+    if ! self.split_read_write {
+      return;
+    }
+    loop {
+      let split: Vec<String> = self.components.schemas.iter()
+        .filter(|(name, schema)| ! self.split.contains(*name) && self.needs_split(schema))
+        .map(|(name, _)| name.clone())
+        .collect();
+      if split.is_empty() {
+        break;
+      }
+      self.split.extend(split);
+    }
+  }
+
+  fn needs_split(&self, val: &openapiv3::ReferenceOr<openapiv3::Schema>) -> bool {
+    let item = match val {
+      openapiv3::ReferenceOr::Reference {reference} => {
+        return reference.strip_prefix("#/components/schemas/").is_some_and(|name| self.split.contains(name));
+      },
+      openapiv3::ReferenceOr::Item(item) => item,
+    };
+    match &item.schema_kind {
+      openapiv3::SchemaKind::Type(openapiv3::Type::Object(obj)) => {
+        let additional = match additional_properties(obj) {
+          Some(openapiv3::AdditionalProperties::Schema(schema)) => self.needs_split(schema),
+          _ => false,
+        };
+        additional || obj.properties.values().any(|prop| {
+          let prop = prop.clone().unbox();
+          self.resolve_schema(&prop).is_ok_and(|schema| schema.schema_data.read_only || schema.schema_data.write_only)
+            || self.needs_split(&prop)
+        })
+      },
+      openapiv3::SchemaKind::Type(openapiv3::Type::Array(arr)) => self.needs_split(&arr.items.clone().unbox()),
+      openapiv3::SchemaKind::OneOf {one_of: members}
+        | openapiv3::SchemaKind::AllOf {all_of: members}
+        | openapiv3::SchemaKind::AnyOf {any_of: members} => members.iter().any(|member| self.needs_split(member)),
+      _ => false,
+    }
+  }
+
+  /// Whether a property is left out of the type being generated: readOnly
+  /// properties from request types and writeOnly ones from response types.
+  fn skip_property(&self, prop: &openapiv3::ReferenceOr<Box<openapiv3::Schema>>) -> bool {
+    if ! self.split_read_write {
+      return false;
+    }
+    match self.resolve_schema(&prop.clone().unbox()) {
+      Ok(schema) if self.input => schema.schema_data.read_only,
+      Ok(schema) => schema.schema_data.write_only,
+      Err(_) => false,
+    }
+  }
+
+  /// Work out the traits of every component schema. References may be
+  /// cyclic, so start by assuming every trait holds and narrow down until
+  /// nothing changes.
+  fn compute_traits(&mut self) {
+    for name in self.components.schemas.keys() {
+      self.traits.insert(name.clone(), TypeTraits::all());
+    }
+    loop {
+      let mut changed = false;
+      for (name, schema) in &self.components.schemas {
+        let traits = self.schema_traits(schema);
+        if self.traits[name] != traits {
+          self.traits.insert(name.clone(), traits);
+          changed = true;
+        }
+      }
+      if ! changed {
+        break;
+      }
+    }
+  }
+
+  /// The traits of the type generated for a schema, mirroring `get_proptype`.
+  fn schema_traits(&self, val: &openapiv3::ReferenceOr<openapiv3::Schema>) -> TypeTraits {
+    let no_default = TypeTraits {default: false, ..TypeTraits::all()};
+    match val {
+      openapiv3::ReferenceOr::Reference {reference} => match self.component_schema(reference) {
+        Some(schema) => self.schema_traits(&schema),
+        None => reference.strip_prefix("#/components/schemas/")
+          .and_then(|name| self.traits.get(name).copied())
+          .unwrap_or_else(TypeTraits::all),
+      },
+      openapiv3::ReferenceOr::Item(item) => match &item.schema_kind {
+        openapiv3::SchemaKind::Type(type_) => match type_ {
+          openapiv3::Type::String(string) if ! string.enumeration.is_empty() => no_default,
+          openapiv3::Type::String(_) | openapiv3::Type::Integer(_) if format_type(type_).is_some() => no_default,
+          openapiv3::Type::String(_) | openapiv3::Type::Integer(_) | openapiv3::Type::Boolean {} => TypeTraits::all(),
+          openapiv3::Type::Number(_) => TypeTraits {eq: false, hash: false, default: true},
+          openapiv3::Type::Object(obj) => self.object_traits(obj),
+          openapiv3::Type::Array(arr) => TypeTraits {
+            default: true,
+            ..self.schema_traits(&arr.items.clone().unbox())
+          },
+        },
+        openapiv3::SchemaKind::OneOf {one_of: variants} | openapiv3::SchemaKind::AnyOf {any_of: variants} => {
+          variants.iter().map(|variant| self.schema_traits(variant)).fold(no_default, TypeTraits::and)
+        },
+        openapiv3::SchemaKind::AllOf {all_of} => self.merge_all_of("", all_of)
+          .map_or_else(|_| TypeTraits::all(), |obj| self.object_traits(&obj)),
+        openapiv3::SchemaKind::Any(_) => TypeTraits {eq: true, hash: false, default: true},
+      },
+    }
+  }
+
+  /// The traits of a struct or map generated for an object. Structs only
+  /// implement `Default` when all of their fields are optional.
+  fn object_traits(&self, val: &openapiv3::ObjectType) -> TypeTraits {
+    let mut res = TypeTraits::all();
+    for (prop_name, prop) in &val.properties {
+      let optional = ! val.required.contains(prop_name)
+        || self.field_schema(&prop.clone().unbox()).is_some_and(|item| item.schema_data.nullable || default_expr(&item, "").is_some());
+      res = res.and(TypeTraits {
+        default: optional,
+        ..self.schema_traits(&prop.clone().unbox())
+      });
+    }
+    let additional = match additional_properties(val) {
+      None | Some(openapiv3::AdditionalProperties::Any(false)) => None,
+      Some(openapiv3::AdditionalProperties::Any(true)) => Some(TypeTraits::all()),
+      Some(openapiv3::AdditionalProperties::Schema(schema)) => Some(self.schema_traits(schema)),
+    };
+    if let Some(additional) = additional {
+      res = res.and(TypeTraits {hash: false, default: true, ..additional});
+    }
+    res
+  }
+
+  /// The value type for an object's `additionalProperties`, if it allows any.
+  fn additional_properties_type(&mut self, parent_name: &str, prop_name: &str, val: &openapiv3::ObjectType) -> anyhow::Result<Option<String>> {
+    Ok(match additional_properties(val) {
+      None | Some(openapiv3::AdditionalProperties::Any(false)) => None,
+      Some(openapiv3::AdditionalProperties::Any(true)) => Some("serde_json::Value".to_string()),
+      Some(openapiv3::AdditionalProperties::Schema(schema)) => match schema.as_ref() {
+        openapiv3::ReferenceOr::Item(openapiv3::Schema {schema_kind: openapiv3::SchemaKind::Any(_), ..}) => {
+          Some("serde_json::Value".to_string())
+        },
+        schema => {
+          self.indirection += 1;
+          let value_type = self.get_proptype(parent_name, prop_name, schema);
+          self.indirection -= 1;
+          Some(value_type?.to_prop_type())
+        },
+      },
+    })
+  }
+
+  fn integer_type(&self, val: &openapiv3::IntegerType) -> &'static str {
+    let unsigned = self.unsigned && val.minimum.is_some_and(|min| min >= 0);
+    match (&val.format, unsigned) {
+      (openapiv3::VariantOrUnknownOrEmpty::Item(openapiv3::IntegerFormat::Int32), false) => "i32",
+      (openapiv3::VariantOrUnknownOrEmpty::Item(openapiv3::IntegerFormat::Int32), true) => "u32",
+      (_, false) => "i64",
+      (_, true) => "u64",
+    }
+  }
+
+  /// Resolve a schema reference into `components/schemas`, or to the schema
+  /// of a parameter or header, following chained references.
+  fn resolve_schema(&self, val: &openapiv3::ReferenceOr<openapiv3::Schema>) -> anyhow::Result<openapiv3::Schema> {
+    match val {
+      openapiv3::ReferenceOr::Item(item) => Ok(item.clone()),
+      openapiv3::ReferenceOr::Reference {reference} => {
+        if let Some(schema) = self.component_schema(reference) {
+          return self.resolve_schema(&schema);
+        }
+        let name = reference.strip_prefix("#/components/schemas/")
+          .ok_or_else(|| anyhow::anyhow!("Schema reference {} INVALID", reference))?;
+        let schema = self.components.schemas.get(name)
+          .ok_or_else(|| anyhow::anyhow!("Schema reference {} NOT FOUND", reference))?;
+        self.resolve_schema(schema)
+      },
+    }
+  }
+
+  /// The schema of the parameter or header a reference into
+  /// `components/parameters` or `components/headers` points at.
+  fn component_schema(&self, reference: &str) -> Option<openapiv3::ReferenceOr<openapiv3::Schema>> {
+    if let Some(name) = reference.strip_prefix("#/components/parameters/") {
+      let parameter = self.resolve_parameter(self.components.parameters.get(name)?).ok()?;
+      return Some(parameter_schema(parameter_data(&parameter)).unbox());
+    }
+    let name = reference.strip_prefix("#/components/headers/")?;
+    let header = match self.components.headers.get(name)? {
+      openapiv3::ReferenceOr::Item(header) => header,
+      openapiv3::ReferenceOr::Reference {reference} => return self.component_schema(reference),
+    };
+    Some(header_schema(header).unbox())
+  }
+
+  /// The inline schema giving a field its docs, nullability and default:
+  /// the field's own, or that of the parameter or header it refers to.
+  fn field_schema(&self, val: &openapiv3::ReferenceOr<openapiv3::Schema>) -> Option<openapiv3::Schema> {
+    match val {
+      openapiv3::ReferenceOr::Item(item) => Some(item.clone()),
+      openapiv3::ReferenceOr::Reference {reference} => match self.component_schema(reference)? {
+        openapiv3::ReferenceOr::Item(item) => Some(item),
+        openapiv3::ReferenceOr::Reference {..} => None,
+      },
+    }
+  }
+
+  fn resolve_parameter(&self, val: &openapiv3::ReferenceOr<openapiv3::Parameter>) -> anyhow::Result<openapiv3::Parameter> {
+    match val {
+      openapiv3::ReferenceOr::Item(item) => Ok(item.clone()),
+      openapiv3::ReferenceOr::Reference {reference} => {
+        let name = reference.strip_prefix("#/components/parameters/")
+          .ok_or_else(|| anyhow::anyhow!("Parameter reference {} INVALID", reference))?;
+        let parameter = self.components.parameters.get(name)
+          .ok_or_else(|| anyhow::anyhow!("Parameter reference {} NOT FOUND", reference))?;
+        self.resolve_parameter(parameter)
+      },
+    }
+  }
+
+  fn resolve_response(&self, val: &openapiv3::ReferenceOr<openapiv3::Response>) -> anyhow::Result<openapiv3::Response> {
+    match val {
+      openapiv3::ReferenceOr::Item(item) => Ok(item.clone()),
+      openapiv3::ReferenceOr::Reference {reference} => {
+        let name = reference.strip_prefix("#/components/responses/")
+          .ok_or_else(|| anyhow::anyhow!("Response reference {} INVALID", reference))?;
+        let response = self.components.responses.get(name)
+          .ok_or_else(|| anyhow::anyhow!("Response reference {} NOT FOUND", reference))?;
+        self.resolve_response(response)
+      },
+    }
+  }
+
+  fn resolve_request_body(&self, val: &openapiv3::ReferenceOr<openapiv3::RequestBody>) -> anyhow::Result<openapiv3::RequestBody> {
+    match val {
+      openapiv3::ReferenceOr::Item(item) => Ok(item.clone()),
+      openapiv3::ReferenceOr::Reference {reference} => {
+        let name = reference.strip_prefix("#/components/requestBodies/")
+          .ok_or_else(|| anyhow::anyhow!("Request body reference {} INVALID", reference))?;
+        let request_body = self.components.request_bodies.get(name)
+          .ok_or_else(|| anyhow::anyhow!("Request body reference {} NOT FOUND", reference))?;
+        self.resolve_request_body(request_body)
+      },
+    }
+  }
+
+  /// Flatten `allOf` members into a single object, unioning properties and
+  /// required lists. Properties declared by several members must agree on type.
+  fn merge_all_of(&self, name: &str, val: &[openapiv3::ReferenceOr<openapiv3::Schema>]) -> anyhow::Result<openapiv3::ObjectType> {
+    let mut merged = openapiv3::ObjectType::default();
+    for member in val {
+      let obj = match self.resolve_schema(member)?.schema_kind {
+        openapiv3::SchemaKind::Type(openapiv3::Type::Object(obj)) => obj,
+        openapiv3::SchemaKind::AllOf {all_of} => self.merge_all_of(name, &all_of)?,
+        openapiv3::SchemaKind::Any(any) => openapiv3::ObjectType {
+          properties: any.properties,
+          required: any.required,
+          additional_properties: any.additional_properties,
+          min_properties: any.min_properties,
+          max_properties: any.max_properties,
+        },
+        kind => anyhow::bail!("AllOf {} member is not an object: {:?}", name, kind),
+      };
+
+      for (prop_name, prop) in obj.properties {
+        match merged.properties.get(&prop_name) {
+          Some(existing) if ! self.same_schema_type(existing, &prop) => {
+            anyhow::bail!("AllOf {} has conflicting types for property {}", name, prop_name);
+          },
+          Some(_) => {},
+          None => { merged.properties.insert(prop_name, prop); },
+        }
+      }
+      for prop_name in obj.required {
+        if ! merged.required.contains(&prop_name) {
+          merged.required.push(prop_name);
+        }
+      }
+      if merged.additional_properties.is_none() {
+        merged.additional_properties = obj.additional_properties;
+      }
+    }
+    Ok(merged)
+  }
+
+  /// Whether two property schemas describe the same type once references
+  /// are resolved, ignoring descriptive data at any depth.
+  fn same_schema_type(&self, a: &openapiv3::ReferenceOr<Box<openapiv3::Schema>>, b: &openapiv3::ReferenceOr<Box<openapiv3::Schema>>) -> bool {
+    if let (openapiv3::ReferenceOr::Reference {reference: a}, openapiv3::ReferenceOr::Reference {reference: b}) = (a, b) {
+      if a == b {
+        return true;
+      }
+    }
+    let structure = |val: &openapiv3::ReferenceOr<Box<openapiv3::Schema>>| {
+      let mut structure = serde_json::to_value(self.resolve_schema(&val.clone().unbox()).ok()?).ok()?;
+      strip_docs(&mut structure);
+      Some(structure)
+    };
+    match (structure(a), structure(b)) {
+      (Some(a), Some(b)) => a == b,
+      _ => false,
+    }
+  }
+
+  fn new_anytypedef(&mut self, name: &str, data: &openapiv3::SchemaData) {
+    self.untyped.push(name.to_string());
+    let name = type_ident(name);
+    let attr = self.raw_type_prefix(&name, data);
+    self.cur_scope_or_module().raw(&format!("{}pub type {} = serde_json::Value;", attr, name));
+  }
+}
+
+/// Tarjan's algorithm, returning components in reverse topological order.
+fn strongly_connected(graph: &BTreeMap<String, BTreeSet<String>>) -> Vec<BTreeSet<String>> {
+  struct State<'a> {
+    index: HashMap<&'a str, usize>,
+    low: HashMap<&'a str, usize>,
+    stack: Vec<&'a str>,
+    on_stack: HashSet<&'a str>,
+    res: Vec<BTreeSet<String>>,
+  }
+
+  fn visit<'a>(graph: &'a BTreeMap<String, BTreeSet<String>>, node: &'a str, state: &mut State<'a>) {
+    let index = state.index.len();
+    state.index.insert(node, index);
+    state.low.insert(node, index);
+    state.stack.push(node);
+    state.on_stack.insert(node);
+    for next in graph[node].iter().filter(|next| graph.contains_key(*next)) {
+      if ! state.index.contains_key(next.as_str()) {
+        visit(graph, next, state);
+        let low = state.low[node].min(state.low[next.as_str()]);
+        state.low.insert(node, low);
+      } else if state.on_stack.contains(next.as_str()) {
+        let low = state.low[node].min(state.index[next.as_str()]);
+        state.low.insert(node, low);
+      }
+    }
+    if state.low[node] == index {
+      let mut component = BTreeSet::new();
+      while let Some(member) = state.stack.pop() {
+        state.on_stack.remove(member);
+        component.insert(member.to_string());
+        if member == node {
+          break;
+        }
+      }
+      state.res.push(component);
+    }
+  }
+
+  let mut state = State {index: HashMap::new(), low: HashMap::new(), stack: vec![], on_stack: HashSet::new(), res: vec![]};
+  for node in graph.keys() {
+    if ! state.index.contains_key(node.as_str()) {
+      visit(graph, node, &mut state);
+    }
+  }
+  state.res
+}
+
+/// Collect the edges within `component` that lead back to a node on the
+/// depth-first search stack.
+fn back_edges(graph: &BTreeMap<String, BTreeSet<String>>, component: &BTreeSet<String>, node: &str, visited: &mut BTreeSet<String>, stack: &mut Vec<String>, res: &mut BTreeSet<(String, String)>) {
+  visited.insert(node.to_string());
+  stack.push(node.to_string());
+  for next in graph[node].iter().filter(|next| component.contains(*next)) {
+    if stack.contains(next) {
+      res.insert((node.to_string(), next.clone()));
+    } else if ! visited.contains(next) {
+      back_edges(graph, component, next, visited, stack, res);
+    }
+  }
+  stack.pop();
+}
+
+/// A `#[deprecated]` attribute, with GitHub's deprecation and removal dates
+/// in the note when known.
+fn deprecated_attr(deprecation_date: Option<&str>, removal_date: Option<&str>) -> String {
+  let note = match (deprecation_date, removal_date) {
+    (Some(deprecated), Some(removal)) => format!("deprecated by GitHub on {}, to be removed on {}", deprecated, removal),
+    (Some(deprecated), None) => format!("deprecated by GitHub on {}", deprecated),
+    (None, Some(removal)) => format!("deprecated by GitHub, to be removed on {}", removal),
+    (None, None) => "deprecated by GitHub".to_string(),
+  };
+  format!("#[deprecated(note = \"{}\")]", note)
+}
+
+fn deprecated_schema_attr(data: &openapiv3::SchemaData) -> Option<String> {
+  data.deprecated.then(|| deprecated_attr(None, None))
+}
+
+/// A `#[deprecated]` attribute for a deprecated operation, with the dates
+/// from its `x-github` extension.
+fn operation_deprecated_attr(operation: &openapiv3::Operation) -> Option<String> {
+  let x_github = operation.extensions.get("x-github");
+  let date = |key: &str| x_github.and_then(|x_github| x_github.get(key)).and_then(|date| date.as_str());
+  let (deprecation_date, removal_date) = (date("deprecationDate"), date("removalDate"));
+  (operation.deprecated || deprecation_date.is_some() || removal_date.is_some())
+    .then(|| deprecated_attr(deprecation_date, removal_date))
+}
+
+/// The `OperationMeta` literal for an operation, from its `x-github`
+/// extension.
+fn operation_meta(method: &str, path: &str, operation_id: &str, operation: &openapiv3::Operation) -> String {
+  let x_github = operation.extensions.get("x-github");
+  let get = |key: &str| x_github.and_then(|x_github| x_github.get(key));
+  let str_option = |value: Option<&serde_json::Value>| match value.and_then(|value| value.as_str()) {
+    Some(value) => format!("Some({:?})", value),
+    None => "None".to_string(),
+  };
+  let flag = |key: &str| get(key).and_then(|value| value.as_bool()).unwrap_or(false);
+  let tags: Vec<String> = operation.tags.iter().map(|tag| format!("{:?}", tag)).collect();
+  let previews: Vec<String> = get("previews")
+    .and_then(|previews| previews.as_array())
+    .into_iter()
+    .flatten()
+    .map(|preview| format!(
+      "crate::metadata::Preview {{name: {:?}, required: {}, note: {}}}",
+      preview.get("name").and_then(|name| name.as_str()).unwrap_or_default(),
+      preview.get("required").and_then(|required| required.as_bool()).unwrap_or(false),
+      str_option(preview.get("note")),
+    ))
+    .collect();
+  [
+    "crate::metadata::OperationMeta {".to_string(),
+    format!("    operation_id: {:?},", operation_id),
+    format!("    method: {:?},", method.to_uppercase()),
+    format!("    path: {:?},", path),
+    format!("    tags: &[{}],", tags.join(", ")),
+    format!("    category: {},", str_option(get("category"))),
+    format!("    subcategory: {},", str_option(get("subcategory"))),
+    format!("    github_cloud_only: {},", flag("githubCloudOnly")),
+    format!("    enabled_for_github_apps: {},", flag("enabledForGitHubApps")),
+    format!("    previews: &[{}],", previews.join(", ")),
+    format!("    deprecated: {},", operation.deprecated),
+    format!("    deprecation_date: {},", str_option(get("deprecationDate"))),
+    format!("    removal_date: {},", str_option(get("removalDate"))),
+    "},".to_string(),
+  ].join("\n")
+}
+
+/// Schema data to document the types of an operation with its summary,
+/// description and external docs.
+fn operation_schema_data(operation: &openapiv3::Operation) -> openapiv3::SchemaData {
+  let description = [&operation.summary, &operation.description]
+    .into_iter()
+    .flatten()
+    .map(|v| v.trim())
+    .filter(|v| ! v.is_empty())
+    .collect::<Vec<_>>()
+    .join("\n\n");
+  openapiv3::SchemaData {
+    description: Some(description).filter(|v| ! v.is_empty()),
+    external_docs: operation.external_docs.clone(),
+    ..Default::default()
+  }
+}
+
+fn parameter_data(parameter: &openapiv3::Parameter) -> &openapiv3::ParameterData {
+  match parameter {
+    openapiv3::Parameter::Query {parameter_data, ..}
+      | openapiv3::Parameter::Header {parameter_data, ..}
+      | openapiv3::Parameter::Path {parameter_data, ..}
+      | openapiv3::Parameter::Cookie {parameter_data, ..} => parameter_data,
+  }
+}
+
+/// The schema of a parameter, documented with the parameter's description
+/// and example.
+fn parameter_schema(data: &openapiv3::ParameterData) -> openapiv3::ReferenceOr<Box<openapiv3::Schema>> {
+  documented_schema(&data.format, &data.description, &data.example, data.deprecated)
+}
+
+/// The schema of a header, documented with the header's description and
+/// example.
+fn header_schema(header: &openapiv3::Header) -> openapiv3::ReferenceOr<Box<openapiv3::Schema>> {
+  documented_schema(&header.format, &header.description, &header.example, header.deprecated)
+}
+
+fn documented_schema(format: &openapiv3::ParameterSchemaOrContent, description: &Option<String>, example: &Option<serde_json::Value>, deprecated: Option<bool>) -> openapiv3::ReferenceOr<Box<openapiv3::Schema>> {
+  match format {
+    openapiv3::ParameterSchemaOrContent::Schema(openapiv3::ReferenceOr::Item(schema)) => {
+      let mut schema = schema.clone();
+      if schema.schema_data.description.is_none() {
+        schema.schema_data.description = description.clone();
+      }
+      if schema.schema_data.example.is_none() {
+        schema.schema_data.example = example.clone();
+      }
+      schema.schema_data.deprecated |= deprecated.unwrap_or(false);
+      openapiv3::ReferenceOr::boxed_item(schema)
+    },
+    openapiv3::ParameterSchemaOrContent::Schema(openapiv3::ReferenceOr::Reference {reference}) => {
+      openapiv3::ReferenceOr::Reference {reference: reference.clone()}
+    },
+    openapiv3::ParameterSchemaOrContent::Content(_) => openapiv3::ReferenceOr::boxed_item(openapiv3::Schema {
+      schema_data: openapiv3::SchemaData {description: description.clone(), ..Default::default()},
+      schema_kind: openapiv3::SchemaKind::Any(Default::default()),
+    }),
+  }
+}
+
+/// A variant name for a status code, after its reason phrase.
+fn status_variant_name(status: u16) -> String {
+  let reason = match status {
+    100 => "Continue",
+    101 => "SwitchingProtocols",
+    200 => "Ok",
+    201 => "Created",
+    202 => "Accepted",
+    203 => "NonAuthoritativeInformation",
+    204 => "NoContent",
+    205 => "ResetContent",
+    206 => "PartialContent",
+    300 => "MultipleChoices",
+    301 => "MovedPermanently",
+    302 => "Found",
+    303 => "SeeOther",
+    304 => "NotModified",
+    307 => "TemporaryRedirect",
+    308 => "PermanentRedirect",
+    400 => "BadRequest",
+    401 => "Unauthorized",
+    402 => "PaymentRequired",
+    403 => "Forbidden",
+    404 => "NotFound",
+    405 => "MethodNotAllowed",
+    406 => "NotAcceptable",
+    409 => "Conflict",
+    410 => "Gone",
+    411 => "LengthRequired",
+    412 => "PreconditionFailed",
+    413 => "PayloadTooLarge",
+    415 => "UnsupportedMediaType",
+    422 => "UnprocessableEntity",
+    423 => "Locked",
+    428 => "PreconditionRequired",
+    429 => "TooManyRequests",
+    451 => "UnavailableForLegalReasons",
+    500 => "InternalServerError",
+    501 => "NotImplemented",
+    502 => "BadGateway",
+    503 => "ServiceUnavailable",
+    504 => "GatewayTimeout",
+    _ => return format!("Status{}", status),
+  };
+  reason.to_string()
+}
+
+/// Whether a media type is JSON, e.g. `application/json` or
+/// `application/scim+json`.
+fn is_json(content_type: &str) -> bool {
+  let essence = content_type.split(';').next().unwrap_or_default().trim();
+  essence == "application/json" || essence.ends_with("+json")
+}
+
+/// Title an untitled bare array or string body, or such a `oneOf` variant,
+/// after the parameter GitHub documents it as, to name its type or variant.
+fn name_bare_body(item: &mut openapiv3::Schema, parameter_name: &str) {
+  if let openapiv3::SchemaKind::OneOf {one_of} | openapiv3::SchemaKind::AnyOf {any_of: one_of} = &mut item.schema_kind {
+    for variant in one_of {
+      if let openapiv3::ReferenceOr::Item(variant) = variant {
+        name_bare_body(variant, parameter_name);
+      }
+    }
+  } else if matches!(item.schema_kind, openapiv3::SchemaKind::Type(openapiv3::Type::Array(_) | openapiv3::Type::String(_))) {
+    item.schema_data.title.get_or_insert_with(|| parameter_name.to_string());
+  }
+}
+
+/// The operations of a path item, with their HTTP methods.
+fn path_operations(item: &openapiv3::PathItem) -> Vec<(&'static str, &openapiv3::Operation)> {
+  [
+    ("get", &item.get),
+    ("put", &item.put),
+    ("post", &item.post),
+    ("delete", &item.delete),
+    ("options", &item.options),
+    ("head", &item.head),
+    ("patch", &item.patch),
+    ("trace", &item.trace),
+  ]
+    .into_iter()
+    .filter_map(|(method, operation)| operation.as_ref().map(|operation| (method, operation)))
+    .collect()
+}
+
+/// Docs for a struct or enum, falling back to `fallback` when the schema has
+/// none.
+fn type_doc(data: &openapiv3::SchemaData, fallback: Option<&str>) -> Option<String> {
+  let doc = schema_doc(data);
+  if doc.is_empty() {
+    fallback.map(|v| v.to_string())
+  } else {
+    Some(doc.join("\n"))
+  }
+}
+
+/// Doc comment lines from a schema's title, description, external docs and
+/// example.
+fn schema_doc(data: &openapiv3::SchemaData) -> Vec<String> {
+  let mut res = vec![];
+  let description = data.description.as_deref().map(str::trim).filter(|v| ! v.is_empty());
+  if let Some(title) = data.title.as_deref().map(str::trim).filter(|v| ! v.is_empty()) {
+    if description != Some(title) {
+      res.extend(wrap_markdown(title));
+    }
+  }
+  if let Some(description) = description {
+    if ! res.is_empty() {
+      res.push("".to_string());
+    }
+    res.extend(wrap_markdown(description));
+  }
+  if let Some(external_docs) = &data.external_docs {
+    if ! res.is_empty() {
+      res.push("".to_string());
+    }
+    let link = match external_docs.description.as_deref().map(str::trim).filter(|v| ! v.is_empty()) {
+      Some(description) => format!("See [{}]({}).", description, external_docs.url),
+      None => format!("See <{}>.", external_docs.url),
+    };
+    res.extend(wrap_markdown(&link));
+  }
+  if let Some(example) = &data.example {
+    if ! res.is_empty() {
+      res.push("".to_string());
+    }
+    res.push("# Example".to_string());
+    res.push("".to_string());
+    res.push("```json".to_string());
+    res.extend(serde_json::to_string_pretty(example).unwrap_or_default().lines().map(|v| v.to_string()));
+    res.push("```".to_string());
+  }
+  res
+}
+
+const DOC_WIDTH: usize = 80;
+
+/// Wrap markdown to `DOC_WIDTH` columns for doc comments, leaving code blocks,
+/// tables and headings alone. Code blocks are marked as `text` so rustdoc
+/// doesn't run them as doctests.
+fn wrap_markdown(text: &str) -> Vec<String> {
+  let mut res = vec![];
+  let mut in_fence = false;
+  // Indentation of the text of the list item being continued, if any
+  let mut item_indent: Option<String> = None;
+  for line in text.lines() {
+    let line = line.trim_end();
+    let trimmed = line.trim_start();
+    if trimmed.is_empty() {
+      item_indent = None;
+    }
+    if trimmed.starts_with("```") {
+      item_indent = None;
+      if ! in_fence && trimmed == "```" {
+        res.push(format!("{}```text", &line[..line.len() - trimmed.len()]));
+      } else {
+        res.push(line.to_string());
+      }
+      in_fence = ! in_fence;
+      continue;
+    }
+    if in_fence || trimmed.starts_with('|') || trimmed.starts_with('#') {
+      item_indent = None;
+      res.push(line.to_string());
+      continue;
+    }
+    // Four spaces would start an indented code block
+    let mut indent = line[..(line.len() - trimmed.len()).min(3)].to_string();
+    let marker = trimmed.split_whitespace().next()
+      .filter(|word| matches!(*word, "-" | "*" | "+") || (word.ends_with('.') && word[..word.len() - 1].chars().all(|c| c.is_ascii_digit()) && word.len() > 1))
+      .map_or(0, |word| word.len() + 1);
+    // Lines continuing a list item are indented to its text, so they aren't
+    // read as a lazy continuation
+    match &item_indent {
+      Some(item) if marker == 0 && indent.len() < item.len() => indent = item.clone(),
+      _ => (),
+    }
+    let continuation = format!("{}{}", indent, " ".repeat(marker));
+    if marker > 0 || item_indent.is_some() {
+      item_indent = Some(continuation.clone());
+    }
+    let indent = indent.as_str();
+    let mut current = indent.to_string();
+    for word in trimmed.split_whitespace() {
+      if current.trim().is_empty() {
+        current.push_str(word);
+      } else if current.len() + 1 + word.len() > DOC_WIDTH {
+        res.push(current);
+        current = format!("{}{}", continuation, word);
+      } else {
+        current.push(' ');
+        current.push_str(word);
+      }
+    }
+    res.push(if current.trim().is_empty() { "".to_string() } else { current });
+  }
+  res
+}
+
+/// Variant names for a string enum, in the order of its values. `Unknown` is
+/// kept for the catch-all variant.
+fn string_enum_variants(values: &[String]) -> Vec<String> {
+  let mut variant_names: HashSet<String> = HashSet::new();
+  variant_names.insert("Unknown".to_string());
+  values.iter().map(|value| unique_name(&mut variant_names, &type_ident(value))).collect()
+}
+
+/// A Rust expression for a schema's `default`, for the type `get_proptype`
+/// generates for it. `enum_name` is the name of its string enum, if any.
+/// Nullable schemas keep `None` for missing values.
+fn default_expr(val: &openapiv3::Schema, enum_name: &str) -> Option<String> {
+  let default = val.schema_data.default.as_ref()?;
+  if val.schema_data.nullable {
+    return None;
+  }
+  match (&val.schema_kind, default) {
+    (openapiv3::SchemaKind::Type(openapiv3::Type::Boolean {}), serde_json::Value::Bool(value)) => Some(value.to_string()),
+    (openapiv3::SchemaKind::Type(openapiv3::Type::Integer(_)), serde_json::Value::Number(value)) if value.is_i64() => {
+      Some(value.to_string())
+    },
+    (openapiv3::SchemaKind::Type(openapiv3::Type::Number(_)), serde_json::Value::Number(value)) => {
+      value.as_f64().map(|value| format!("{:?}", value))
+    },
+    (openapiv3::SchemaKind::Type(type_ @ openapiv3::Type::String(string)), serde_json::Value::String(value)) => {
+      if ! string.enumeration.is_empty() {
+        let index = string.enumeration.iter().position(|v| v == value)?;
+        Some(format!("{}::{}", enum_name, string_enum_variants(&string.enumeration)[index]))
+      } else {
+        match format_type(type_) {
+          None => Some(format!("{:?}.to_string()", value)),
+          Some(("crate::uri::Uri", _)) => Some(format!("{:?}.parse().unwrap()", value)),
+          Some(_) => None,
+        }
+      }
+    },
+    _ => None,
+  }
+}
+
+/// Remove the trailing space codegen2 writes on blank doc comment lines.
+fn trim_blank_doc_lines(code: &str) -> String {
+  code.lines()
+    .map(|line| if line.trim() == "///" { line.trim_end() } else { line })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// Drop `null` from `enum` lists, which openapiv3 can't parse as strings,
+/// making their schemas nullable instead.
+fn strip_null_enum_values(val: &mut serde_json::Value) {
+  match val {
+    serde_json::Value::Object(map) => {
+      if let Some(serde_json::Value::Array(values)) = map.get_mut("enum") {
+        if values.iter().any(|value| value.is_null()) {
+          values.retain(|value| ! value.is_null());
+          map.insert("nullable".to_string(), serde_json::Value::Bool(true));
+        }
+      }
+      for value in map.values_mut() {
+        strip_null_enum_values(value);
+      }
+    },
+    serde_json::Value::Array(values) => {
+      for value in values {
+        strip_null_enum_values(value);
+      }
+    },
+    _ => {},
+  }
+}
+
+/// Remove the parts of a schema that only end up in docs, so that schemas
+/// generating the same type compare equal.
+fn strip_docs(schema: &mut serde_json::Value) {
+  let map = match schema {
+    serde_json::Value::Object(map) => map,
+    _ => return,
+  };
+  for key in ["title", "description", "example", "externalDocs"] {
+    map.remove(key);
+  }
+  for (key, value) in map.iter_mut() {
+    match (key.as_str(), value) {
+      ("properties", serde_json::Value::Object(props)) => props.values_mut().for_each(strip_docs),
+      ("items" | "additionalProperties" | "not", value) => strip_docs(value),
+      ("oneOf" | "anyOf" | "allOf", serde_json::Value::Array(members)) => members.iter_mut().for_each(strip_docs),
+      _ => {},
+    }
+  }
+}
+
+/// A `&str` expression for a JSON pointer from its segments, the first
+/// being the pointer of the value being validated.
+fn pointer_expr(segments: &[String]) -> String {
+  match segments {
+    [pointer] => pointer.clone(),
+    _ => format!("&format!({:?}, {})", vec!["{}"; segments.len()].join("/"), segments.join(", ")),
+  }
+}
+
+/// Whether a type has nothing to validate beyond its own constraints, so
+/// `Validate` needn't look inside it.
+fn is_plain_type(type_: &str) -> bool {
+  for wrapper in ["Option<", "Vec<", "Box<", "HashMap<String, "] {
+    if let Some(inner) = type_.strip_prefix(wrapper).and_then(|v| v.strip_suffix('>')) {
+      return is_plain_type(inner);
+    }
+  }
+  matches!(type_, "String" | "bool" | "i32" | "i64" | "u32" | "u64" | "f32" | "f64" | "serde_json::Value")
+    || ["crate::datetime::", "crate::uri::", "crate::email::"].iter().any(|prefix| type_.starts_with(prefix))
+}
+
+/// The type a variant's accessors and `From` impl work with, and the
+/// expression converting `value` for them, seeing through a `Box`.
+fn unbox(type_: &str, boxed: bool, boxed_value: &str) -> (String, String) {
+  match type_.strip_prefix("Box<").and_then(|v| v.strip_suffix('>')) {
+    Some(inner) if boxed => (inner.to_string(), boxed_value.to_string()),
+    _ => (type_.to_string(), "value".to_string()),
+  }
+}
+
+fn number_type(val: &openapiv3::NumberType) -> &'static str {
+  match val.format {
+    openapiv3::VariantOrUnknownOrEmpty::Item(openapiv3::NumberFormat::Float) => "f32",
+    _ => "f64",
+  }
+}
+
+/// Map string and integer formats to the dedicated types in the crate's
+/// support modules, along with the module used to (de)serialize them if the
+/// type's own serde implementation can't be used.
+fn format_type(val: &openapiv3::Type) -> Option<(&'static str, Option<&'static str>)> {
+  match val {
+    openapiv3::Type::String(string) => match &string.format {
+      openapiv3::VariantOrUnknownOrEmpty::Item(openapiv3::StringFormat::DateTime) => {
+        Some(("crate::datetime::DateTime", Some("crate::datetime::rfc3339")))
+      },
+      openapiv3::VariantOrUnknownOrEmpty::Item(openapiv3::StringFormat::Date) => {
+        Some(("crate::datetime::Date", Some("crate::datetime::date")))
+      },
+      openapiv3::VariantOrUnknownOrEmpty::Unknown(format) => match format.as_str() {
+        "uri" => Some(("crate::uri::Uri", None)),
+        "uri-template" => Some(("crate::uri::UriTemplate", None)),
+        "email" => Some(("crate::email::Email", None)),
+        _ => None,
+      },
+      _ => None,
+    },
+    openapiv3::Type::Integer(integer) => match &integer.format {
+      openapiv3::VariantOrUnknownOrEmpty::Unknown(format) if format == "timestamp" => {
+        Some(("crate::datetime::Timestamp", Some("crate::datetime::unix")))
+      },
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+/// An object's `additionalProperties`. An object with neither properties
+/// nor `additionalProperties` is free-form, allowing any.
+fn additional_properties(val: &openapiv3::ObjectType) -> Option<&openapiv3::AdditionalProperties> {
+  static ANY: openapiv3::AdditionalProperties = openapiv3::AdditionalProperties::Any(true);
+  match &val.additional_properties {
+    None if val.properties.is_empty() => Some(&ANY),
+    additional => additional.as_ref(),
+  }
+}
+
+/// Whether an object is a plain map, with `additionalProperties` but no named properties.
+fn is_map(val: &openapiv3::ObjectType) -> bool {
+  val.properties.is_empty() && matches!(
+    additional_properties(val),
+    Some(openapiv3::AdditionalProperties::Any(true) | openapiv3::AdditionalProperties::Schema(_))
+  )
+}
+
+/// Name a `oneOf`/`anyOf` variant after its title, the schema it references
+/// or its primitive kind.
+fn variant_name(val: &openapiv3::ReferenceOr<openapiv3::Schema>) -> String {
+  let item = match val {
+    openapiv3::ReferenceOr::Reference {reference} => return type_ident(reference.rsplit('/').next().unwrap()),
+    openapiv3::ReferenceOr::Item(item) => item,
+  };
+  if let Some(title) = &item.schema_data.title {
+    return type_ident(title);
+  }
+  match &item.schema_kind {
+    openapiv3::SchemaKind::Type(openapiv3::Type::String(_)) => "String",
+    openapiv3::SchemaKind::Type(openapiv3::Type::Number(_)) => "Number",
+    openapiv3::SchemaKind::Type(openapiv3::Type::Integer(_)) => "Integer",
+    openapiv3::SchemaKind::Type(openapiv3::Type::Boolean {}) => "Boolean",
+    openapiv3::SchemaKind::Type(openapiv3::Type::Array(_)) => "Array",
+    openapiv3::SchemaKind::Type(openapiv3::Type::Object(obj)) if is_map(obj) => "Map",
+    openapiv3::SchemaKind::Type(openapiv3::Type::Object(_)) => "Object",
+    openapiv3::SchemaKind::OneOf {..} => "OneOf",
+    openapiv3::SchemaKind::AllOf {..} => "AllOf",
+    openapiv3::SchemaKind::AnyOf {..} => "AnyOf",
+    openapiv3::SchemaKind::Any(_) => "Value",
+  }.to_string()
+}
+
+/// Claim `name` in `names`, adding a numeric suffix if it is already taken.
+fn unique_name(names: &mut HashSet<String>, name: &str) -> String {
+  let mut res = name.to_string();
+  let mut suffix = 2;
+  while names.contains(&res) {
+    res = format!("{}{}", name, suffix);
+    suffix += 1;
+  }
+  names.insert(res.clone());
+  res
+}
+
+/// Rust keywords, strict, reserved and weak, that can't be used as plain
+/// identifiers.
+const KEYWORDS: [&str; 54] = [
+  "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for", "if", "impl", "in",
+  "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super",
+  "trait", "true", "type", "unsafe", "use", "where", "while", "async", "await", "dyn", "abstract", "become", "box",
+  "do", "final", "macro", "override", "priv", "typeof", "unsized", "virtual", "yield", "try", "gen", "union",
+  "macro_rules",
+];
+
+/// Turn any name from the spec into an identifier in the given case,
+/// spelling out reactions (`+1`, `reactions--1`), dropping other characters
+/// that can't appear in identifiers and prefixing names that start with a
+/// digit. Keywords become raw identifiers, or get a trailing `_` where Rust
+/// doesn't allow that.
+fn ident(value: &str, case: Case) -> String {
+  let words = match value {
+    "-1" => "minus one".to_string(),
+    _ => value.replace("+1", " plus one ").replace("--1", " minus one "),
+  };
+  let name = words
+    .chars()
+    .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { ' ' })
+    .collect::<String>()
+    .to_case(case);
+  let name = match name.chars().next() {
+    None => "empty".to_case(case),
+    Some(c) if c.is_ascii_digit() && case == Case::Snake => format!("value_{}", name),
+    Some(c) if c.is_ascii_digit() => format!("Value{}", name),
+    _ => name,
+  };
+  match name.as_str() {
+    "crate" | "self" | "Self" | "super" | "_" => format!("{}_", name),
+    _ if KEYWORDS.contains(&name.as_str()) => format!("r#{}", name),
+    _ => name,
+  }
+}
+
+/// A PascalCase identifier for a type or enum variant.
+fn type_ident(value: &str) -> String {
+  ident(value, Case::Pascal)
+}
+
+/// A snake_case identifier for a field, function or module.
+fn field_ident(value: &str) -> String {
+  ident(value, Case::Snake)
+}
+
+/// An identifier without its raw `r#` prefix, to build other identifiers from.
+fn unraw(ident: &str) -> &str {
+  ident.strip_prefix("r#").unwrap_or(ident)
+}
+
+fn main() -> anyhow::Result<()> {
+  env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+
+  let flags = Flags::parse();
+  let api_spec = Path::new(&flags.api_spec);
+
+  let mut spec: serde_json::Value = match api_spec.extension().and_then(|v| v.to_str()) {
+    Some("json") => {
+      serde_json::from_reader(File::open(api_spec).unwrap()).map_err(anyhow::Error::from)
+    },
+    Some("yaml") => {
+      serde_yaml::from_reader(File::open(api_spec).unwrap()).map_err(anyhow::Error::from)
+    },
+    Some(ext) => Result::Err(anyhow::anyhow!("Invalid file extension {:?}", ext)),
+    None => Result::Err(anyhow::anyhow!("Invalid file type {:?}", api_spec))
+  }?;
+  strip_null_enum_values(&mut spec);
+  let openapi: OpenAPI = serde_json::from_value(spec)?;
+
+  if let Some(components) = openapi.components {
+    let mut builder = Builder::new(components.clone());
+    builder.unsigned = flags.unsigned;
+    for derive in &flags.derive {
+      if ! derive.is_empty() && ! DERIVES.contains(&derive.as_str()) {
+        anyhow::bail!("Cannot derive {}, expected one of {}", derive, DERIVES.join(", "));
+      }
+    }
+    if flags.derive.iter().any(|v| v == "Eq") && ! flags.derive.iter().any(|v| v == "PartialEq") {
+      anyhow::bail!("Deriving Eq requires PartialEq");
+    }
+    builder.derives = flags.derive.clone();
+    builder.compute_traits();
+    builder.compute_boxes();
+    builder.split_read_write = flags.split_read_write;
+    builder.compute_split();
+    builder.scope.raw("#![allow(non_camel_case_types, dead_code, unused_imports, clippy::large_enum_variant, clippy::new_without_default, clippy::too_many_arguments, deprecated)]");
+    builder.scope.raw("pub mod datetime;\npub mod email;\npub mod metadata;\npub mod nullable;\npub mod response;\npub mod uri;\npub mod validate;");
+    builder.new_type_module(&["components", "schemas"]);
+    builder.claim_component_names()?;
+    for (name, schema) in &components.schemas {
+      builder.current = name.clone();
+      builder.new_schema(&builder.component_names[name].clone(), schema)?;
+      if builder.split.contains(name) {
+        builder.input = true;
+        builder.new_schema(&builder.component_names[&format!("{}-Input", name)].clone(), schema)?;
+        builder.input = false;
+      }
+    }
+
+    builder.input = true;
+    builder.new_type_module(&["components", "parameters"]);
+    for (name, parameter) in &components.parameters {
+      builder.current = name.clone();
+      builder.new_parameter(name, parameter)?;
+    }
+    builder.input = false;
+
+    builder.new_type_module(&["components", "headers"]);
+    for (name, header) in &components.headers {
+      builder.current = name.clone();
+      builder.new_header(name, header)?;
+    }
+
+    builder.new_type_module(&["components", "responses"]);
+    for (name, response) in &components.responses {
+      builder.current = name.clone();
+      builder.new_shared_response(name, response)?;
+    }
+
+    builder.new_type_module(&["operations"]);
+    for (path, path_item) in &openapi.paths {
+      let path_item = match path_item {
+        openapiv3::ReferenceOr::Item(item) => item,
+        openapiv3::ReferenceOr::Reference {reference} => {
+          warn!("Path {} is a reference to {}, skipping it", path, reference);
+          continue;
+        },
+      };
+      for (method, operation) in path_operations(path_item) {
+        builder.new_operation(method, path, path_item, operation)?;
+      }
+    }
+    builder.new_operation_table();
+
+    if ! builder.split.is_empty() {
+      info!("{} schemas have readOnly or writeOnly properties and were split into request (Input) and response types:\n  {}", builder.split.len(), builder.split.iter().cloned().collect::<Vec<_>>().join("\n  "));
+    }
+
+    if ! builder.untyped.is_empty() {
+      warn!("{} schemas have no type and were generated as serde_json::Value:\n  {}", builder.untyped.len(), builder.untyped.join("\n  "));
     }
 
+    let code = trim_blank_doc_lines(&builder.scope.to_string());
     if flags.out_file == "-" {
-      println!("{}", builder.scope.to_string());
+      println!("{}", code);
     } else {
       File::create(flags.out_file)?
-        .write_all(format!("{}\n", builder.scope.to_string()).as_bytes())?;
+        .write_all(format!("{}\n", code).as_bytes())?;
     }
   }
 
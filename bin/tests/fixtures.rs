@@ -0,0 +1,106 @@
+//! Generate crates from the specs in `tests/fixtures` and check that they
+//! compile, and that their tests pass.
+
+use std::{
+  fs,
+  path::Path,
+  process::Command,
+};
+
+/// Generate a crate for `tests/fixtures/<name>.yaml`, alongside the support
+/// modules of `github-openapi`, and `cargo check` it. With a
+/// `tests/fixtures/<name>.rs`, run that as a test of the crate instead.
+fn check_fixture(name: &str) {
+  let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+  let root = manifest_dir.parent().unwrap();
+  let fixtures = root.join("target").join("fixtures");
+  let dir = fixtures.join(name);
+  let src = dir.join("src");
+  fs::create_dir_all(&src).unwrap();
+
+  for entry in fs::read_dir(root.join("lib").join("src")).unwrap() {
+    let path = entry.unwrap().path();
+    let file_name = path.file_name().unwrap();
+    if file_name != "lib.rs" {
+      fs::copy(&path, src.join(file_name)).unwrap();
+    }
+  }
+  let manifest = fs::read_to_string(root.join("lib").join("Cargo.toml")).unwrap()
+    .replacen("name = \"github-openapi\"", &format!("name = \"fixture-{}\"", name), 1);
+  fs::write(dir.join("Cargo.toml"), format!("{}\n[workspace]\n", manifest)).unwrap();
+  // Build with the dependency versions the workspace was built with
+  if root.join("Cargo.lock").exists() {
+    fs::copy(root.join("Cargo.lock"), dir.join("Cargo.lock")).unwrap();
+  }
+
+  let status = Command::new(env!("CARGO_BIN_EXE_github-openapi-builder"))
+    .arg(manifest_dir.join("tests").join("fixtures").join(format!("{}.yaml", name)))
+    .arg(src.join("lib.rs"))
+    .status()
+    .unwrap();
+  assert!(status.success(), "generating {} failed", name);
+  let code = fs::read_to_string(src.join("lib.rs")).unwrap();
+  assert!(! code.lines().any(|line| line.ends_with(' ')), "the code generated for {} has trailing whitespace", name);
+
+  let test = manifest_dir.join("tests").join("fixtures").join(format!("{}.rs", name));
+  let mut cargo = Command::new(env!("CARGO"));
+  if test.exists() {
+    fs::create_dir_all(dir.join("tests")).unwrap();
+    fs::copy(&test, dir.join("tests").join(format!("{}.rs", name))).unwrap();
+    cargo.args(["test", "--test", name]);
+  } else {
+    cargo.arg("check");
+  }
+  let status = cargo
+    .args(["--offline", "--quiet", "--manifest-path"])
+    .arg(dir.join("Cargo.toml"))
+    .env("CARGO_TARGET_DIR", fixtures.join("target"))
+    .status()
+    .unwrap();
+  assert!(status.success(), "the crate generated for {} doesn't compile or its tests fail", name);
+}
+
+#[test]
+fn all_of_docs() {
+  check_fixture("all_of_docs");
+}
+
+#[test]
+fn cycles() {
+  check_fixture("cycles");
+}
+
+#[test]
+fn defaults() {
+  check_fixture("defaults");
+}
+
+#[test]
+fn inline_reuse() {
+  check_fixture("inline_reuse");
+}
+
+#[test]
+fn null_enum() {
+  check_fixture("null_enum");
+}
+
+#[test]
+fn one_of_dates() {
+  check_fixture("one_of_dates");
+}
+
+#[test]
+fn params() {
+  check_fixture("params");
+}
+
+#[test]
+fn responses() {
+  check_fixture("responses");
+}
+
+#[test]
+fn validate() {
+  check_fixture("validate");
+}
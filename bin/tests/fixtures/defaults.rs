@@ -0,0 +1,24 @@
+use fixture_defaults::components::schemas::{AllDefaults, AllDefaultsState, RequiredNullable, Settings};
+use serde_json::json;
+
+#[test]
+fn fills_in_required_fields() {
+  let value: AllDefaults = serde_json::from_value(json!({})).unwrap();
+  assert_eq!(value, AllDefaults::default());
+  assert!(value.flag);
+  assert_eq!(value.name, "octocat");
+  assert_eq!(value.state, AllDefaultsState::Open);
+
+  let value = RequiredNullable::default();
+  assert_eq!(value.x, None);
+  assert_eq!(value.d, 5);
+}
+
+#[test]
+fn leaves_out_optional_fields() {
+  let value = Settings::new();
+  assert_eq!(value.private, None);
+  assert_eq!(serde_json::to_value(&value).unwrap(), json!({}));
+  let value: Settings = serde_json::from_value(json!({})).unwrap();
+  assert_eq!(value, Settings::new());
+}
@@ -0,0 +1,9 @@
+use fixture_null_enum::components::schemas::{Alert, Resolution};
+
+#[test]
+fn decodes_null_and_values() {
+  let alert: Alert = serde_json::from_str(r#"{"resolution": null}"#).unwrap();
+  assert_eq!(alert.resolution, None);
+  let alert: Alert = serde_json::from_str(r#"{"resolution": "wont_fix"}"#).unwrap();
+  assert_eq!(alert.resolution, Some(Resolution::WontFix));
+}
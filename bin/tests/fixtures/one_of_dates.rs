@@ -0,0 +1,9 @@
+use fixture_one_of_dates::components::schemas::Event;
+
+#[test]
+fn round_trips_each_format() {
+  for value in [r#"{"at":"2011-01-26T19:01:12Z"}"#, r#"{"at":1296068472}"#] {
+    let event: Event = serde_json::from_str(value).unwrap();
+    assert_eq!(serde_json::to_string(&event).unwrap(), value);
+  }
+}
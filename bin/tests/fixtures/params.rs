@@ -0,0 +1,11 @@
+use fixture_params::operations::{MetaRootParams, ThingsListParams, ThingsListParamsQuery};
+use serde_json::json;
+
+#[test]
+fn has_only_the_listed_parameters() {
+  // Struct literals don't compile with any other fields
+  let params = MetaRootParams {};
+  assert_eq!(serde_json::to_value(params).unwrap(), json!({}));
+  let params = ThingsListParams {query: ThingsListParamsQuery {per_page: Some(30)}};
+  assert_eq!(serde_json::to_value(params).unwrap(), json!({"query": {"per_page": 30}}));
+}
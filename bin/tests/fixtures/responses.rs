@@ -0,0 +1,18 @@
+use fixture_responses::operations::ThingsCreateResponse;
+
+#[test]
+fn decodes_documented_statuses() {
+  match ThingsCreateResponse::from_status_and_body(201, br#"{"id": 1}"#).unwrap() {
+    ThingsCreateResponse::Created(body) => assert_eq!(body["id"], 1),
+    response => panic!("unexpected {:?}", response),
+  }
+  assert!(matches!(ThingsCreateResponse::from_status_and_body(202, b"{}").unwrap(), ThingsCreateResponse::Accepted(_)));
+  assert!(matches!(ThingsCreateResponse::from_status_and_body(204, b"").unwrap(), ThingsCreateResponse::NoContent));
+  assert!(matches!(ThingsCreateResponse::from_status_and_body(404, b"{}").unwrap(), ThingsCreateResponse::NotFound(_)));
+}
+
+#[test]
+fn rejects_undocumented_statuses_and_bad_bodies() {
+  assert!(ThingsCreateResponse::from_status_and_body(500, b"{}").is_err());
+  assert!(ThingsCreateResponse::from_status_and_body(201, b"[]").is_err());
+}
@@ -0,0 +1,31 @@
+use fixture_validate::{
+  components::schemas::{validate_tags, Grid},
+  operations::{LabelsAddRequest, LabelsAddRequest0},
+  validate::{Validate, Violation},
+};
+
+fn pointers(violations: Vec<Violation>) -> Vec<String> {
+  violations.into_iter().map(|violation| violation.pointer).collect()
+}
+
+#[test]
+fn checks_array_items() {
+  let mut grid = Grid::new(vec![vec![0, -1], vec![-2]]);
+  grid.tags = Some(vec!["ok".to_string(), "toolong".to_string()]);
+  assert_eq!(pointers(grid.validate()), ["/rows/0/1", "/rows/1/0", "/tags/1"]);
+}
+
+#[test]
+fn checks_oneof_variants() {
+  let request = LabelsAddRequest::Array(vec!["toolong".to_string()]);
+  assert_eq!(pointers(request.validate()), ["/0"]);
+  let request = LabelsAddRequest::Object(LabelsAddRequest0 {labels: Some(vec!["toolong".to_string()])});
+  assert_eq!(pointers(request.validate()), ["/labels/0"]);
+}
+
+#[test]
+fn checks_aliases() {
+  let mut violations = vec![];
+  validate_tags(&vec!["a".to_string(), "b".to_string(), "toolong".to_string()], "", &mut violations);
+  assert_eq!(pointers(violations), ["", "/2"]);
+}
@@ -0,0 +1,271 @@
+//! Date and time types for `format: date-time`, `date` and `timestamp` schemas.
+//!
+//! Enable either the `chrono` or the `time` feature to get typed values. With
+//! neither enabled, dates are left as `String` and timestamps as `i64`.
+
+#[cfg(all(feature = "chrono", feature = "time"))]
+compile_error!("features `chrono` and `time` are mutually exclusive");
+
+#[cfg(feature = "chrono")]
+pub type DateTime = chrono::DateTime<chrono::Utc>;
+#[cfg(feature = "chrono")]
+pub type Date = chrono::NaiveDate;
+#[cfg(feature = "chrono")]
+pub type Timestamp = chrono::DateTime<chrono::Utc>;
+
+#[cfg(feature = "time")]
+pub type DateTime = time::OffsetDateTime;
+#[cfg(feature = "time")]
+pub type Date = time::Date;
+#[cfg(feature = "time")]
+pub type Timestamp = time::OffsetDateTime;
+
+#[cfg(not(any(feature = "chrono", feature = "time")))]
+pub type DateTime = String;
+#[cfg(not(any(feature = "chrono", feature = "time")))]
+pub type Date = String;
+#[cfg(not(any(feature = "chrono", feature = "time")))]
+pub type Timestamp = i64;
+
+/// Adds an `option` submodule wrapping the parent module's `serialize` and
+/// `deserialize` for `Option<$ty>` fields, and a `nullable` submodule for
+/// `Option<Option<$ty>>` fields (see [`crate::nullable`]).
+macro_rules! option_module {
+  ($ty:ty) => {
+    pub mod option {
+      use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+      pub fn serialize<S: Serializer>(value: &Option<$ty>, serializer: S) -> Result<S::Ok, S::Error> {
+        struct Wrapper<'a>(&'a $ty);
+
+        impl Serialize for Wrapper<'_> {
+          fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            super::serialize(self.0, serializer)
+          }
+        }
+
+        match value {
+          Some(value) => serializer.serialize_some(&Wrapper(value)),
+          None => serializer.serialize_none(),
+        }
+      }
+
+      pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<$ty>, D::Error> {
+        #[derive(Deserialize)]
+        struct Wrapper(#[serde(with = "super")] $ty);
+
+        Option::<Wrapper>::deserialize(deserializer).map(|value| value.map(|value| value.0))
+      }
+    }
+
+    pub mod nullable {
+      use serde::{Deserializer, Serializer};
+
+      pub fn serialize<S: Serializer>(value: &Option<Option<$ty>>, serializer: S) -> Result<S::Ok, S::Error> {
+        super::option::serialize(value.as_ref().unwrap_or(&None), serializer)
+      }
+
+      pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Option<$ty>>, D::Error> {
+        super::option::deserialize(deserializer).map(Some)
+      }
+    }
+  };
+}
+
+/// RFC 3339 date-times, e.g. `2011-01-26T19:01:12Z`.
+pub mod rfc3339 {
+  #[cfg(feature = "time")]
+  pub use time::serde::rfc3339::{serialize, deserialize};
+
+  #[cfg(not(feature = "time"))]
+  pub fn serialize<S: serde::Serializer>(value: &super::DateTime, serializer: S) -> Result<S::Ok, S::Error> {
+    serde::Serialize::serialize(value, serializer)
+  }
+
+  #[cfg(not(feature = "time"))]
+  pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<super::DateTime, D::Error> {
+    serde::Deserialize::deserialize(deserializer)
+  }
+
+  option_module!(super::super::DateTime);
+}
+
+/// Calendar dates, e.g. `2011-01-26`.
+pub mod date {
+  #[cfg(feature = "time")]
+  time::serde::format_description!(format, Date, "[year]-[month]-[day]");
+  #[cfg(feature = "time")]
+  pub use format::{serialize, deserialize};
+
+  #[cfg(not(feature = "time"))]
+  pub fn serialize<S: serde::Serializer>(value: &super::Date, serializer: S) -> Result<S::Ok, S::Error> {
+    serde::Serialize::serialize(value, serializer)
+  }
+
+  #[cfg(not(feature = "time"))]
+  pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<super::Date, D::Error> {
+    serde::Deserialize::deserialize(deserializer)
+  }
+
+  option_module!(super::super::Date);
+}
+
+/// Seconds since the unix epoch, e.g. `1296068472`.
+pub mod unix {
+  #[cfg(feature = "chrono")]
+  pub use chrono::serde::ts_seconds::{serialize, deserialize};
+
+  #[cfg(feature = "time")]
+  pub use time::serde::timestamp::{serialize, deserialize};
+
+  #[cfg(not(any(feature = "chrono", feature = "time")))]
+  pub fn serialize<S: serde::Serializer>(value: &super::Timestamp, serializer: S) -> Result<S::Ok, S::Error> {
+    serde::Serialize::serialize(value, serializer)
+  }
+
+  #[cfg(not(any(feature = "chrono", feature = "time")))]
+  pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<super::Timestamp, D::Error> {
+    serde::Deserialize::deserialize(deserializer)
+  }
+
+  option_module!(super::super::Timestamp);
+}
+
+#[cfg(test)]
+mod tests {
+  use serde::{Deserialize, Serialize};
+  use serde_json::json;
+
+  #[derive(Debug, PartialEq, Serialize, Deserialize)]
+  struct Record {
+    #[serde(with = "super::rfc3339")]
+    date_time: super::DateTime,
+    #[serde(with = "super::date")]
+    date: super::Date,
+    #[serde(with = "super::unix")]
+    timestamp: super::Timestamp,
+    #[serde(with = "super::rfc3339::option")]
+    option_date_time: Option<super::DateTime>,
+    #[serde(with = "super::date::option")]
+    option_date: Option<super::Date>,
+    #[serde(with = "super::unix::option")]
+    option_timestamp: Option<super::Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none", default, with = "super::rfc3339::nullable")]
+    nullable_date_time: Option<Option<super::DateTime>>,
+    #[serde(skip_serializing_if = "Option::is_none", default, with = "super::date::nullable")]
+    nullable_date: Option<Option<super::Date>>,
+    #[serde(skip_serializing_if = "Option::is_none", default, with = "super::unix::nullable")]
+    nullable_timestamp: Option<Option<super::Timestamp>>,
+  }
+
+  /// `2011-01-26T19:01:12Z`, `2011-01-26` and the same instant as a timestamp.
+  #[cfg(feature = "chrono")]
+  fn values() -> (super::DateTime, super::Date, super::Timestamp) {
+    let date_time: chrono::DateTime<chrono::Utc> = "2011-01-26T19:01:12Z".parse().unwrap();
+    (date_time, chrono::NaiveDate::from_ymd_opt(2011, 1, 26).unwrap(), date_time)
+  }
+
+  /// `2011-01-26T19:01:12Z`, `2011-01-26` and the same instant as a timestamp.
+  #[cfg(feature = "time")]
+  fn values() -> (super::DateTime, super::Date, super::Timestamp) {
+    let date_time = time::macros::datetime!(2011-01-26 19:01:12 UTC);
+    (date_time, time::macros::date!(2011-01-26), date_time)
+  }
+
+  /// `2011-01-26T19:01:12Z`, `2011-01-26` and the same instant as a timestamp.
+  #[cfg(not(any(feature = "chrono", feature = "time")))]
+  fn values() -> (super::DateTime, super::Date, super::Timestamp) {
+    ("2011-01-26T19:01:12Z".to_string(), "2011-01-26".to_string(), 1296068472)
+  }
+
+  fn round_trip(value: serde_json::Value, expected: Record) {
+    let record: Record = serde_json::from_value(value.clone()).unwrap();
+    assert_eq!(record, expected);
+    assert_eq!(serde_json::to_value(&record).unwrap(), value);
+  }
+
+  #[test]
+  fn values_round_trip() {
+    let (date_time, date, timestamp) = values();
+    let (option_date_time, option_date, option_timestamp) = values();
+    let (nullable_date_time, nullable_date, nullable_timestamp) = values();
+    round_trip(json!({
+      "date_time": "2011-01-26T19:01:12Z",
+      "date": "2011-01-26",
+      "timestamp": 1296068472,
+      "option_date_time": "2011-01-26T19:01:12Z",
+      "option_date": "2011-01-26",
+      "option_timestamp": 1296068472,
+      "nullable_date_time": "2011-01-26T19:01:12Z",
+      "nullable_date": "2011-01-26",
+      "nullable_timestamp": 1296068472,
+    }), Record {
+      date_time,
+      date,
+      timestamp,
+      option_date_time: Some(option_date_time),
+      option_date: Some(option_date),
+      option_timestamp: Some(option_timestamp),
+      nullable_date_time: Some(Some(nullable_date_time)),
+      nullable_date: Some(Some(nullable_date)),
+      nullable_timestamp: Some(Some(nullable_timestamp)),
+    });
+  }
+
+  #[test]
+  fn nulls_round_trip() {
+    let (date_time, date, timestamp) = values();
+    round_trip(json!({
+      "date_time": "2011-01-26T19:01:12Z",
+      "date": "2011-01-26",
+      "timestamp": 1296068472,
+      "option_date_time": null,
+      "option_date": null,
+      "option_timestamp": null,
+      "nullable_date_time": null,
+      "nullable_date": null,
+      "nullable_timestamp": null,
+    }), Record {
+      date_time,
+      date,
+      timestamp,
+      option_date_time: None,
+      option_date: None,
+      option_timestamp: None,
+      nullable_date_time: Some(None),
+      nullable_date: Some(None),
+      nullable_timestamp: Some(None),
+    });
+  }
+
+  #[test]
+  fn absent_nullables_round_trip() {
+    let (date_time, date, timestamp) = values();
+    round_trip(json!({
+      "date_time": "2011-01-26T19:01:12Z",
+      "date": "2011-01-26",
+      "timestamp": 1296068472,
+      "option_date_time": null,
+      "option_date": null,
+      "option_timestamp": null,
+    }), Record {
+      date_time,
+      date,
+      timestamp,
+      option_date_time: None,
+      option_date: None,
+      option_timestamp: None,
+      nullable_date_time: None,
+      nullable_date: None,
+      nullable_timestamp: None,
+    });
+  }
+
+  #[test]
+  fn rejects_malformed_dates() {
+    #[derive(Debug, Deserialize)]
+    struct Timestamp(#[serde(with = "super::unix")] #[allow(dead_code)] super::Timestamp);
+
+    assert!(serde_json::from_value::<Timestamp>(json!("2011-01-26T19:01:12Z")).is_err());
+  }
+}
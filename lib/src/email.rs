@@ -0,0 +1,38 @@
+//! Type for `format: email` schemas.
+
+use std::fmt;
+
+use serde::{Serialize, Deserialize};
+
+/// An email address, kept exactly as GitHub sent it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Email(String);
+
+impl Email {
+  pub fn new(email: impl Into<String>) -> Self {
+    Email(email.into())
+  }
+
+  pub fn as_str(&self) -> &str {
+    &self.0
+  }
+}
+
+impl fmt::Display for Email {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.0)
+  }
+}
+
+impl From<String> for Email {
+  fn from(email: String) -> Self {
+    Email(email)
+  }
+}
+
+impl From<&str> for Email {
+  fn from(email: &str) -> Self {
+    Email(email.to_string())
+  }
+}
@@ -0,0 +1,82 @@
+//! Static metadata about every operation of the API, from its `x-github`
+//! extension, for reasoning about calls without the OpenAPI spec.
+//!
+//! The table itself is [`crate::operations::OPERATIONS`].
+
+use crate::operations::OPERATIONS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OperationMeta {
+  pub operation_id: &'static str,
+  /// The HTTP method, in upper case, e.g. `GET`.
+  pub method: &'static str,
+  /// The path template, e.g. `/repos/{owner}/{repo}`.
+  pub path: &'static str,
+  pub tags: &'static [&'static str],
+  pub category: Option<&'static str>,
+  pub subcategory: Option<&'static str>,
+  pub github_cloud_only: bool,
+  pub enabled_for_github_apps: bool,
+  pub previews: &'static [Preview],
+  pub deprecated: bool,
+  /// When GitHub deprecated the operation, e.g. `2020-01-21`.
+  pub deprecation_date: Option<&'static str>,
+  /// When GitHub will remove the operation.
+  pub removal_date: Option<&'static str>,
+}
+
+/// An API preview, enabled with a custom media type in the `Accept` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Preview {
+  pub name: &'static str,
+  /// Whether the operation only works with the preview enabled.
+  pub required: bool,
+  pub note: Option<&'static str>,
+}
+
+/// Find an operation by its `operationId`, e.g. `repos/get`.
+pub fn by_operation_id(operation_id: &str) -> Option<&'static OperationMeta> {
+  OPERATIONS.iter().find(|operation| operation.operation_id == operation_id)
+}
+
+/// Find an operation by HTTP method and path. The path is either a path
+/// template or a request path, whose segments fill in the template's
+/// parameters, one segment each. Templates matching with a literal segment
+/// win over those with a parameter in its place. A query string is ignored.
+///
+/// ```
+/// # use github_openapi::metadata::by_method_and_path;
+/// let operation = by_method_and_path("get", "/repos/octocat/hello-world/releases/latest").unwrap();
+/// assert_eq!(operation.operation_id, "repos/get-latest-release");
+/// let operation = by_method_and_path("GET", "/repos/{owner}/{repo}/releases/{release_id}").unwrap();
+/// assert_eq!(operation.operation_id, "repos/get-release");
+/// ```
+pub fn by_method_and_path(method: &str, path: &str) -> Option<&'static OperationMeta> {
+  let path = path.split('?').next().unwrap_or_default();
+  let segments: Vec<&str> = path.split('/').collect();
+  OPERATIONS.iter()
+    .filter(|operation| operation.method.eq_ignore_ascii_case(method))
+    .filter_map(|operation| match_segments(operation.path, &segments).map(|literals| (literals, operation)))
+    .max_by(|(a, _), (b, _)| a.cmp(b))
+    .map(|(_, operation)| operation)
+}
+
+/// Match path segments against a template, giving whether each segment
+/// matched literally.
+fn match_segments(template: &str, segments: &[&str]) -> Option<Vec<bool>> {
+  let template: Vec<&str> = template.split('/').collect();
+  if template.len() != segments.len() {
+    return None;
+  }
+  template.iter().zip(segments)
+    .map(|(expected, segment)| {
+      if expected == segment {
+        Some(true)
+      } else if expected.starts_with('{') && expected.ends_with('}') && ! segment.is_empty() {
+        Some(false)
+      } else {
+        None
+      }
+    })
+    .collect()
+}
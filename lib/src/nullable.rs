@@ -0,0 +1,52 @@
+//! Serde helpers for optional and nullable fields.
+//!
+//! Such fields are generated as `Option<Option<T>>` so that a missing field
+//! (`None`) can be told apart from an explicit `null` (`Some(None)`), e.g. to
+//! leave a milestone unchanged versus clearing it in a PATCH body. Missing
+//! fields are skipped when serializing and `Some(None)` is written as `null`.
+
+use serde::{Deserialize, Deserializer};
+
+/// Deserialize a present field, whether `null` or not, as `Some(_)`. Combine
+/// with `#[serde(default)]` so that a missing field becomes `None`.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+  T: Deserialize<'de>,
+  D: Deserializer<'de>,
+{
+  Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+  use serde::{Deserialize, Serialize};
+  use serde_json::json;
+
+  /// A field the way it's generated for an optional, nullable property.
+  #[derive(Debug, PartialEq, Serialize, Deserialize)]
+  struct Patch {
+    #[serde(skip_serializing_if = "Option::is_none", default, deserialize_with = "super::deserialize")]
+    milestone: Option<Option<u32>>,
+  }
+
+  fn round_trip(value: serde_json::Value, expected: Patch) {
+    let patch: Patch = serde_json::from_value(value.clone()).unwrap();
+    assert_eq!(patch, expected);
+    assert_eq!(serde_json::to_value(&patch).unwrap(), value);
+  }
+
+  #[test]
+  fn absent() {
+    round_trip(json!({}), Patch {milestone: None});
+  }
+
+  #[test]
+  fn null() {
+    round_trip(json!({"milestone": null}), Patch {milestone: Some(None)});
+  }
+
+  #[test]
+  fn value() {
+    round_trip(json!({"milestone": 1}), Patch {milestone: Some(Some(1))});
+  }
+}
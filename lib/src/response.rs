@@ -0,0 +1,30 @@
+//! Errors decoding responses with the `from_status_and_body` functions of
+//! the `<Op>Response` enums.
+
+use std::{error, fmt};
+
+#[derive(Debug)]
+pub enum ResponseError {
+  /// The operation doesn't document the status code.
+  UndocumentedStatus(u16),
+  /// The body doesn't decode as the type documented for the status code.
+  Body(u16, serde_json::Error),
+}
+
+impl fmt::Display for ResponseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ResponseError::UndocumentedStatus(status) => write!(f, "undocumented status code {}", status),
+      ResponseError::Body(status, error) => write!(f, "invalid body for status code {}: {}", status, error),
+    }
+  }
+}
+
+impl error::Error for ResponseError {
+  fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+    match self {
+      ResponseError::UndocumentedStatus(_) => None,
+      ResponseError::Body(_, error) => Some(error),
+    }
+  }
+}
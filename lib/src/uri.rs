@@ -0,0 +1,232 @@
+//! Types for `format: uri` and `format: uri-template` schemas.
+//!
+//! Enable the `url` feature to parse URIs into `url::Url`, otherwise they are
+//! left as `String`.
+
+use std::fmt;
+
+use serde::{Serialize, Deserialize};
+
+#[cfg(feature = "url")]
+pub type Uri = url::Url;
+#[cfg(not(feature = "url"))]
+pub type Uri = String;
+
+/// A URI template such as `https://api.github.com/users/octocat/following{/other_user}`.
+///
+/// Supports [RFC 6570] expansion up to level 3.
+///
+/// [RFC 6570]: https://datatracker.ietf.org/doc/html/rfc6570
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct UriTemplate(String);
+
+impl UriTemplate {
+  pub fn new(template: impl Into<String>) -> Self {
+    UriTemplate(template.into())
+  }
+
+  pub fn as_str(&self) -> &str {
+    &self.0
+  }
+
+  /// Expand the template with the given variables. Variables missing from
+  /// `vars` are left out of the result, as RFC 6570 requires for undefined
+  /// values.
+  ///
+  /// ```
+  /// # use github_openapi::uri::UriTemplate;
+  /// let template = UriTemplate::new("https://api.github.com/users/octocat/following{/other_user}");
+  /// assert_eq!(template.expand(&[]), "https://api.github.com/users/octocat/following");
+  /// assert_eq!(
+  ///   template.expand(&[("other_user", "hubot")]),
+  ///   "https://api.github.com/users/octocat/following/hubot",
+  /// );
+  /// ```
+  pub fn expand(&self, vars: &[(&str, &str)]) -> String {
+    let mut res = String::with_capacity(self.0.len());
+    let mut rest = self.0.as_str();
+    while let Some(start) = rest.find('{') {
+      res.push_str(&rest[..start]);
+      match rest[start..].find('}') {
+        Some(end) => {
+          expand_expression(&rest[start + 1..start + end], vars, &mut res);
+          rest = &rest[start + end + 1..];
+        },
+        None => {
+          rest = &rest[start..];
+          break;
+        },
+      }
+    }
+    res.push_str(rest);
+    res
+  }
+}
+
+impl fmt::Display for UriTemplate {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.0)
+  }
+}
+
+impl From<String> for UriTemplate {
+  fn from(template: String) -> Self {
+    UriTemplate(template)
+  }
+}
+
+impl From<&str> for UriTemplate {
+  fn from(template: &str) -> Self {
+    UriTemplate(template.to_string())
+  }
+}
+
+/// Expand a single `{...}` expression, following the table in RFC 6570 appendix A.
+fn expand_expression(expression: &str, vars: &[(&str, &str)], res: &mut String) {
+  let (op, var_list) = match expression.chars().next() {
+    Some(op @ ('+' | '#' | '.' | '/' | ';' | '?' | '&')) => (Some(op), &expression[1..]),
+    _ => (None, expression),
+  };
+  let (first, sep, named, if_empty, allow_reserved) = match op {
+    None => ("", ",", false, "", false),
+    Some('+') => ("", ",", false, "", true),
+    Some('#') => ("#", ",", false, "", true),
+    Some('.') => (".", ".", false, "", false),
+    Some('/') => ("/", "/", false, "", false),
+    Some(';') => (";", ";", true, "", false),
+    Some('?') => ("?", "&", true, "=", false),
+    Some('&') => ("&", "&", true, "=", false),
+    Some(_) => unreachable!(),
+  };
+
+  let mut defined = vars_in(var_list, vars).peekable();
+  if defined.peek().is_none() {
+    return;
+  }
+  res.push_str(first);
+  for (i, (name, value)) in defined.enumerate() {
+    if i != 0 {
+      res.push_str(sep);
+    }
+    if named {
+      res.push_str(name);
+      if value.is_empty() {
+        res.push_str(if_empty);
+        continue;
+      }
+      res.push('=');
+    }
+    encode(value, allow_reserved, res);
+  }
+}
+
+fn vars_in<'a>(var_list: &'a str, vars: &'a [(&'a str, &'a str)]) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+  var_list
+    .split(',')
+    .filter_map(move |name| vars.iter().find(|(var, _)| *var == name).map(|(_, value)| (name, *value)))
+}
+
+fn encode(value: &str, allow_reserved: bool, res: &mut String) {
+  let bytes = value.as_bytes();
+  let mut i = 0;
+  while i < bytes.len() {
+    let b = bytes[i];
+    let pct_encoded = allow_reserved && b == b'%' && i + 2 < bytes.len()
+      && bytes[i + 1].is_ascii_hexdigit() && bytes[i + 2].is_ascii_hexdigit();
+    if b.is_ascii_alphanumeric() || b"-._~".contains(&b) || (allow_reserved && b":/?#[]@!$&'()*+,;=".contains(&b)) {
+      res.push(b as char);
+    } else if pct_encoded {
+      res.push_str(&value[i..i + 3]);
+      i += 2;
+    } else {
+      res.push_str(&format!("%{:02X}", b));
+    }
+    i += 1;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// The variables of the RFC 6570 examples.
+  const VARS: &[(&str, &str)] = &[
+    ("var", "value"),
+    ("hello", "Hello World!"),
+    ("path", "/foo/bar"),
+    ("empty", ""),
+    ("x", "1024"),
+    ("y", "768"),
+    ("half", "50%"),
+    ("encoded", "a%20b"),
+  ];
+
+  fn expand(template: &str) -> String {
+    UriTemplate::new(template).expand(VARS)
+  }
+
+  #[test]
+  fn simple() {
+    assert_eq!(expand("{var}"), "value");
+    assert_eq!(expand("{hello}"), "Hello%20World%21");
+    assert_eq!(expand("{x,y}"), "1024,768");
+    assert_eq!(expand("{x,empty}"), "1024,");
+  }
+
+  #[test]
+  fn reserved() {
+    assert_eq!(expand("{+path}/here"), "/foo/bar/here");
+    assert_eq!(expand("{+hello}"), "Hello%20World!");
+    assert_eq!(expand("here?ref={+path}"), "here?ref=/foo/bar");
+  }
+
+  #[test]
+  fn fragment() {
+    assert_eq!(expand("{#x,hello,y}"), "#1024,Hello%20World!,768");
+    assert_eq!(expand("{#path,x}/here"), "#/foo/bar,1024/here");
+  }
+
+  #[test]
+  fn label_and_path() {
+    assert_eq!(expand("X{.x,y}"), "X.1024.768");
+    assert_eq!(expand("{/var,x}/here"), "/value/1024/here");
+  }
+
+  #[test]
+  fn path_style_parameters() {
+    assert_eq!(expand("{;x,y}"), ";x=1024;y=768");
+    assert_eq!(expand("{;x,y,empty}"), ";x=1024;y=768;empty");
+  }
+
+  #[test]
+  fn query() {
+    assert_eq!(expand("{?x,y}"), "?x=1024&y=768");
+    assert_eq!(expand("{?x,y,empty}"), "?x=1024&y=768&empty=");
+    assert_eq!(expand("?fixed=yes{&x}"), "?fixed=yes&x=1024");
+    assert_eq!(expand("{&x,y,empty}"), "&x=1024&y=768&empty=");
+  }
+
+  #[test]
+  fn undefined_variables() {
+    assert_eq!(expand("{undef}"), "");
+    assert_eq!(expand("a{/undef}b"), "ab");
+    assert_eq!(expand("{?x,undef}"), "?x=1024");
+    assert_eq!(expand("{?undef}"), "");
+    assert_eq!(expand("{;undef,y}"), ";y=768");
+  }
+
+  #[test]
+  fn percent_encoding() {
+    assert_eq!(expand("{half}"), "50%25");
+    assert_eq!(expand("{+half}"), "50%25");
+    assert_eq!(expand("{encoded}"), "a%2520b");
+    assert_eq!(expand("{+encoded}"), "a%20b");
+    assert_eq!(UriTemplate::new("{v}").expand(&[("v", "é/")]), "%C3%A9%2F");
+  }
+
+  #[test]
+  fn unterminated_expression() {
+    assert_eq!(expand("a{var"), "a{var");
+  }
+}
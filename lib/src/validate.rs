@@ -0,0 +1,233 @@
+//! Checking values against the `pattern`, `minLength`/`maxLength`,
+//! `minItems`/`maxItems` and `minimum`/`maximum` constraints of their schemas.
+
+use std::{
+  collections::HashMap,
+  fmt,
+  sync::{Mutex, OnceLock},
+};
+
+use regex::Regex;
+
+/// A value breaking a constraint of its schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+  /// JSON pointer to the value, e.g. `/labels/0/name`.
+  pub pointer: String,
+  pub message: String,
+}
+
+impl fmt::Display for Violation {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}: {}", self.pointer, self.message)
+  }
+}
+
+pub trait Validate {
+  /// Check the value and everything it contains.
+  fn validate(&self) -> Vec<Violation> {
+    let mut violations = vec![];
+    self.validate_at("", &mut violations);
+    violations
+  }
+
+  /// Check the value found at `pointer`, adding what's wrong to `violations`.
+  fn validate_at(&self, pointer: &str, violations: &mut Vec<Violation>);
+}
+
+impl<T: Validate> Validate for Option<T> {
+  fn validate_at(&self, pointer: &str, violations: &mut Vec<Violation>) {
+    if let Some(value) = self {
+      value.validate_at(pointer, violations);
+    }
+  }
+}
+
+impl<T: Validate> Validate for Box<T> {
+  fn validate_at(&self, pointer: &str, violations: &mut Vec<Violation>) {
+    self.as_ref().validate_at(pointer, violations);
+  }
+}
+
+impl<T: Validate> Validate for Vec<T> {
+  fn validate_at(&self, pointer: &str, violations: &mut Vec<Violation>) {
+    for (i, value) in self.iter().enumerate() {
+      value.validate_at(&format!("{}/{}", pointer, i), violations);
+    }
+  }
+}
+
+impl<T: Validate> Validate for HashMap<String, T> {
+  fn validate_at(&self, pointer: &str, violations: &mut Vec<Violation>) {
+    for (key, value) in self {
+      value.validate_at(&format!("{}/{}", pointer, key.replace('~', "~0").replace('/', "~1")), violations);
+    }
+  }
+}
+
+/// Implement `Validate` for types without constraints of their own.
+macro_rules! unconstrained {
+  ($($ty:ty),* $(,)?) => {
+    $(
+      impl Validate for $ty {
+        fn validate_at(&self, _pointer: &str, _violations: &mut Vec<Violation>) {}
+      }
+    )*
+  };
+}
+
+unconstrained!(String, bool, i32, i64, u32, u64, f32, f64, serde_json::Value, crate::email::Email, crate::uri::UriTemplate);
+
+#[cfg(feature = "chrono")]
+unconstrained!(chrono::DateTime<chrono::Utc>, chrono::NaiveDate);
+#[cfg(feature = "time")]
+unconstrained!(time::OffsetDateTime, time::Date);
+#[cfg(feature = "url")]
+unconstrained!(url::Url);
+
+fn violation(pointer: &str, violations: &mut Vec<Violation>, message: String) {
+  violations.push(Violation {pointer: pointer.to_string(), message});
+}
+
+pub fn min_length(value: &str, min: usize, pointer: &str, violations: &mut Vec<Violation>) {
+  let len = value.chars().count();
+  if len < min {
+    violation(pointer, violations, format!("length {} is less than minLength {}", len, min));
+  }
+}
+
+pub fn max_length(value: &str, max: usize, pointer: &str, violations: &mut Vec<Violation>) {
+  let len = value.chars().count();
+  if len > max {
+    violation(pointer, violations, format!("length {} is greater than maxLength {}", len, max));
+  }
+}
+
+pub fn min_items<T>(value: &[T], min: usize, pointer: &str, violations: &mut Vec<Violation>) {
+  if value.len() < min {
+    violation(pointer, violations, format!("{} items is less than minItems {}", value.len(), min));
+  }
+}
+
+pub fn max_items<T>(value: &[T], max: usize, pointer: &str, violations: &mut Vec<Violation>) {
+  if value.len() > max {
+    violation(pointer, violations, format!("{} items is greater than maxItems {}", value.len(), max));
+  }
+}
+
+pub fn minimum(value: f64, min: f64, exclusive: bool, pointer: &str, violations: &mut Vec<Violation>) {
+  if value < min || (exclusive && value == min) {
+    let bound = if exclusive { "exclusiveMinimum" } else { "minimum" };
+    violation(pointer, violations, format!("{} is less than {} {}", value, bound, min));
+  }
+}
+
+pub fn maximum(value: f64, max: f64, exclusive: bool, pointer: &str, violations: &mut Vec<Violation>) {
+  if value > max || (exclusive && value == max) {
+    let bound = if exclusive { "exclusiveMaximum" } else { "maximum" };
+    violation(pointer, violations, format!("{} is greater than {} {}", value, bound, max));
+  }
+}
+
+/// Check `value` against an ECMA 262 `pattern`. The builder leaves out
+/// patterns the `regex` crate can't compile, and any others are skipped.
+pub fn pattern(value: &str, pattern: &'static str, pointer: &str, violations: &mut Vec<Violation>) {
+  static CACHE: OnceLock<Mutex<HashMap<&'static str, Option<Regex>>>> = OnceLock::new();
+  let matches = CACHE.get_or_init(Default::default)
+    .lock()
+    .unwrap()
+    .entry(pattern)
+    .or_insert_with(|| Regex::new(pattern).ok())
+    .as_ref()
+    .map(|regex| regex.is_match(value));
+  if matches == Some(false) {
+    violation(pointer, violations, format!("does not match pattern {}", pattern));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn check(f: impl FnOnce(&mut Vec<Violation>)) -> Vec<String> {
+    let mut violations = vec![];
+    f(&mut violations);
+    violations.iter().map(|violation| violation.to_string()).collect()
+  }
+
+  #[test]
+  fn lengths_count_chars() {
+    assert!(check(|v| min_length("héé", 3, "/name", v)).is_empty());
+    assert_eq!(check(|v| min_length("hé", 3, "/name", v)), ["/name: length 2 is less than minLength 3"]);
+    assert!(check(|v| max_length("héé", 3, "/name", v)).is_empty());
+    assert_eq!(check(|v| max_length("héllo", 3, "/name", v)), ["/name: length 5 is greater than maxLength 3"]);
+  }
+
+  #[test]
+  fn items() {
+    assert!(check(|v| min_items(&[1, 2], 2, "/labels", v)).is_empty());
+    assert_eq!(check(|v| min_items(&[1], 2, "/labels", v)), ["/labels: 1 items is less than minItems 2"]);
+    assert!(check(|v| max_items(&[1, 2], 2, "/labels", v)).is_empty());
+    assert_eq!(check(|v| max_items(&[1, 2, 3], 2, "/labels", v)), ["/labels: 3 items is greater than maxItems 2"]);
+  }
+
+  #[test]
+  fn minimum_inclusive_and_exclusive() {
+    assert!(check(|v| minimum(1.0, 1.0, false, "/n", v)).is_empty());
+    assert_eq!(check(|v| minimum(0.5, 1.0, false, "/n", v)), ["/n: 0.5 is less than minimum 1"]);
+    assert_eq!(check(|v| minimum(1.0, 1.0, true, "/n", v)), ["/n: 1 is less than exclusiveMinimum 1"]);
+    assert!(check(|v| minimum(1.5, 1.0, true, "/n", v)).is_empty());
+  }
+
+  #[test]
+  fn maximum_inclusive_and_exclusive() {
+    assert!(check(|v| maximum(100.0, 100.0, false, "/n", v)).is_empty());
+    assert_eq!(check(|v| maximum(101.0, 100.0, false, "/n", v)), ["/n: 101 is greater than maximum 100"]);
+    assert_eq!(check(|v| maximum(100.0, 100.0, true, "/n", v)), ["/n: 100 is greater than exclusiveMaximum 100"]);
+    assert!(check(|v| maximum(99.5, 100.0, true, "/n", v)).is_empty());
+  }
+
+  #[test]
+  fn pattern_matches_and_caches() {
+    for _ in 0..2 {
+      assert!(check(|v| pattern("abc123", "^[a-z]+[0-9]+$", "/sha", v)).is_empty());
+      assert_eq!(check(|v| pattern("123abc", "^[a-z]+[0-9]+$", "/sha", v)), ["/sha: does not match pattern ^[a-z]+[0-9]+$"]);
+    }
+  }
+
+  #[test]
+  fn pattern_skips_unsupported() {
+    // Lookahead isn't supported by the regex crate
+    assert!(check(|v| pattern("anything", "^(?!foo)", "/name", v)).is_empty());
+  }
+
+  /// Fails with the pointer it's validated at.
+  struct Invalid;
+
+  impl Validate for Invalid {
+    fn validate_at(&self, pointer: &str, violations: &mut Vec<Violation>) {
+      violation(pointer, violations, "invalid".to_string());
+    }
+  }
+
+  #[test]
+  fn containers_extend_the_pointer() {
+    assert_eq!(Invalid.validate(), [Violation {pointer: "".to_string(), message: "invalid".to_string()}]);
+    assert_eq!(check(|v| vec![Invalid, Invalid].validate_at("/labels", v)), ["/labels/0: invalid", "/labels/1: invalid"]);
+    assert_eq!(check(|v| Some(Box::new(Invalid)).validate_at("/label", v)), ["/label: invalid"]);
+    assert!(check(|v| None::<Invalid>.validate_at("/label", v)).is_empty());
+  }
+
+  #[test]
+  fn map_keys_are_escaped() {
+    let map: HashMap<String, Invalid> = [("a/b~c".to_string(), Invalid)].into_iter().collect();
+    assert_eq!(check(|v| map.validate_at("/files", v)), ["/files/a~1b~0c: invalid"]);
+  }
+
+  #[test]
+  fn unconstrained_types_pass() {
+    assert!("x".to_string().validate().is_empty());
+    assert!(42i64.validate().is_empty());
+    assert!(serde_json::json!({"a": 1}).validate().is_empty());
+  }
+}